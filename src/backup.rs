@@ -0,0 +1,251 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{Local, NaiveDateTime};
+use crate::DaysError;
+use crate::event::fnv1a;
+use crate::storage::{get_days_path, replace_file};
+
+/// Name of the directory for backups, in the days directory.
+pub const BACKUPS_DIR_NAME: &str = "backups";
+
+/// How many backups of each events file are kept when not configured.
+pub const DEFAULT_BACKUPS: usize = 10;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3f";
+
+/// A copy of an events file from before it was changed.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Backup {
+    pub created: NaiveDateTime,
+    pub path: PathBuf,
+}
+
+/// Returns the directory for the backups of the events file, creating
+/// it if it does not exist yet. Each events file has its own directory
+/// in the backups directory, named after the file and a hash of its full
+/// path, like `events-1f3b291b`, so that files with the same name in
+/// different places keep their backups apart.
+pub fn get_backups_path(events_path: &Path) -> Result<PathBuf, DaysError> {
+    let path = match get_days_path() {
+        Some(path) => path.join(BACKUPS_DIR_NAME).join(backups_dir_name(events_path)),
+        None => return Err(DaysError::HomeDirectoryNotFound),
+    };
+    if let Err(source) = fs::create_dir_all(&path) {
+        return Err(DaysError::CreateError { path, source });
+    }
+    Ok(path)
+}
+
+fn backups_dir_name(events_path: &Path) -> String {
+    let canonical = canonical_path(events_path);
+    let hash = fnv1a(canonical.to_string_lossy().as_bytes());
+    let name = match events_path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => "events".to_string(),
+    };
+    format!("{}-{:08x}", name, hash)
+}
+
+/// Returns the absolute path of the events file with symbolic links
+/// resolved, also when the file itself does not exist yet.
+fn canonical_path(events_path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(events_path) {
+        return path;
+    }
+    let parent = match events_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (fs::canonicalize(parent), events_path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => events_path.to_path_buf(),
+    }
+}
+
+/// Backups are named after the events file with a timestamp added,
+/// like `events.20261016T153012.345.csv` for `events.csv`.
+fn backup_name(events_path: &Path, created: NaiveDateTime) -> String {
+    let (stem, extension) = split_name(events_path);
+    let mut name = format!("{}.{}", stem, created.format(TIMESTAMP_FORMAT));
+    if let Some(extension) = extension {
+        name.push('.');
+        name.push_str(&extension);
+    }
+    name
+}
+
+fn split_name(events_path: &Path) -> (String, Option<String>) {
+    let stem = match events_path.file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => "events".to_string(),
+    };
+    let extension = events_path.extension().map(|extension| extension.to_string_lossy().to_string());
+    (stem, extension)
+}
+
+/// Returns the time a backup of the events file was made, or `None`
+/// if the file name is not one of its backups.
+fn parse_backup_name(events_path: &Path, name: &str) -> Option<NaiveDateTime> {
+    let (stem, extension) = split_name(events_path);
+    let mut timestamp = name.strip_prefix(&stem)?.strip_prefix('.')?;
+    if let Some(extension) = extension {
+        timestamp = timestamp.strip_suffix(&extension)?.strip_suffix('.')?;
+    }
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()
+}
+
+/// Returns the backups of the events file, newest first.
+pub fn list_backups(events_path: &Path) -> Result<Vec<Backup>, DaysError> {
+    list_backups_in(events_path, &get_backups_path(events_path)?)
+}
+
+fn list_backups_in(events_path: &Path, backups_path: &Path) -> Result<Vec<Backup>, DaysError> {
+    let entries = match fs::read_dir(backups_path) {
+        Ok(entries) => entries,
+        Err(source) => return Err(DaysError::ReadError { path: backups_path.to_path_buf(), source }),
+    };
+
+    let mut backups: Vec<Backup> = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some(created) = parse_backup_name(events_path, &name) {
+            backups.push(Backup { created, path: entry.path() });
+        }
+    }
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Copies the events file to the backups directory and removes the
+/// oldest backups, so that at most `keep` of them remain. Returns the
+/// path of the new backup, or `None` if there is no file to back up yet.
+pub fn create_backup(events_path: &Path, keep: usize) -> Result<Option<PathBuf>, DaysError> {
+    if !events_path.exists() {
+        return Ok(None);
+    }
+
+    let backups_path = get_backups_path(events_path)?;
+    let backup_path = back_up(events_path, &backups_path, Local::now().naive_local(), keep)?;
+    Ok(Some(backup_path))
+}
+
+/// Copies the events file to a backup made at the given time in the
+/// directory, and removes the oldest backups there beyond `keep`.
+fn back_up(events_path: &Path, backups_path: &Path, created: NaiveDateTime, keep: usize) -> Result<PathBuf, DaysError> {
+    let backup_path = backups_path.join(backup_name(events_path, created));
+    if let Err(source) = fs::copy(events_path, &backup_path) {
+        return Err(DaysError::BackupError { path: backup_path, source });
+    }
+
+    for old in list_backups_in(events_path, backups_path)?.iter().skip(keep) {
+        if let Err(source) = fs::remove_file(&old.path) {
+            return Err(DaysError::BackupError { path: old.path.clone(), source });
+        }
+    }
+
+    Ok(backup_path)
+}
+
+/// Finds a backup of the events file by its number in the list of
+/// backups (1 is the newest), its file name or its path.
+pub fn find_backup(events_path: &Path, name: &str) -> Result<Backup, DaysError> {
+    let backups = list_backups(events_path)?;
+    let found = match name.parse::<usize>() {
+        Ok(number) if number > 0 => backups.get(number - 1),
+        _ => backups.iter().find(|backup| backup.path.file_name() == Path::new(name).file_name()),
+    };
+    match found {
+        Some(backup) => Ok(backup.clone()),
+        None => Err(DaysError::BackupNotFound(name.to_string())),
+    }
+}
+
+/// Replaces the events file with the backup. The current contents are
+/// backed up first, so that the restore can be undone, unless `keep` is 0.
+/// Returns the path of that backup, if one was made.
+pub fn restore_backup(backup: &Backup, events_path: &Path, keep: usize) -> Result<Option<PathBuf>, DaysError> {
+    let contents = match fs::read(&backup.path) {
+        Ok(contents) => contents,
+        Err(source) => return Err(DaysError::ReadError { path: backup.path.clone(), source }),
+    };
+
+    let previous = if keep > 0 { create_backup(events_path, keep)? } else { None };
+    replace_file(events_path, &contents)?;
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use chrono::NaiveDate;
+
+    fn time(day: u32, millis: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_milli_opt(22, 40, 25, millis).unwrap()
+    }
+
+    /// A directory of its own in the temporary directory, removed when it is dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("days-backup-{}-{}", std::process::id(), name));
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn backup_names_round_trip() {
+        for (events, name) in [
+            ("events.csv", "events.20261016T224025.672.csv"),
+            ("my.events.json", "my.events.20261016T224025.672.json"),
+            ("events", "events.20261016T224025.672"),
+            (".events", ".events.20261016T224025.672"),
+        ] {
+            let path = Path::new("/somewhere").join(events);
+            assert_eq!(backup_name(&path, time(16, 672)), name);
+            assert_eq!(parse_backup_name(&path, name), Some(time(16, 672)));
+        }
+    }
+
+    #[test]
+    fn recognizes_only_own_backups() {
+        let path = Path::new("my.events.csv");
+        assert_eq!(parse_backup_name(path, "my.events.csv"), None);
+        assert_eq!(parse_backup_name(path, "my.20261016T224025.672.csv"), None);
+        assert_eq!(parse_backup_name(path, "my.events.20261016T224025.672.json"), None);
+        assert_eq!(parse_backup_name(path, "my.events.2026-10-16.csv"), None);
+        assert_eq!(parse_backup_name(Path::new("events"), "events.20261016T224025.672.csv"), None);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_backups() {
+        let dir = TempDir::new("prune");
+        let events_path = dir.0.join("events.csv");
+        let backups_path = dir.0.join("backups");
+        fs::create_dir_all(&backups_path).unwrap();
+        fs::write(&events_path, "date,category,description\n").unwrap();
+        fs::write(backups_path.join("notes.txt"), "").unwrap();
+
+        for day in 1..=5 {
+            back_up(&events_path, &backups_path, time(day, 0), 3).unwrap();
+        }
+        let created: Vec<NaiveDateTime> = list_backups_in(&events_path, &backups_path).unwrap()
+            .into_iter()
+            .map(|backup| backup.created)
+            .collect();
+        assert_eq!(created, [time(5, 0), time(4, 0), time(3, 0)]);
+        assert!(backups_path.join("notes.txt").exists());
+
+        back_up(&events_path, &backups_path, time(6, 0), 0).unwrap();
+        assert!(list_backups_in(&events_path, &backups_path).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+use std::env;
+use std::fmt;
+use chrono::{NaiveDate, Datelike};
+use crate::config::Config;
+use crate::locale::{Locale, Message};
+use crate::recurrence::yearly_date;
+use crate::{Event, EventKind};
+
+/// Events in this category are birthdays: the date is the date of birth
+/// and the description is the name of the person.
+pub const BIRTHDAY_CATEGORY: &str = "birthday";
+
+/// A birthday event with this name is the user's own birthday.
+/// The `BIRTHDATE` environment variable is used if there is none.
+pub const OWN_NAME: &str = "me";
+
+/// The `BIRTHDATE` environment variable has a value that is not a date.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BirthdateError(pub String);
+
+impl fmt::Display for BirthdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Error in the value of the BIRTHDATE environment variable: '{}' is not a valid date.", self.0)
+    }
+}
+
+impl std::error::Error for BirthdateError { }
+
+/// Returns the user's own birthdate from the `BIRTHDATE` environment
+/// variable, or from the configuration file if it is not set.
+pub fn own_birthdate(config: &Config) -> Result<Option<NaiveDate>, BirthdateError> {
+    match env::var("BIRTHDATE") {
+        Ok(value) => match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            Ok(birthdate) => Ok(Some(birthdate)),
+            Err(_) => Err(BirthdateError(value)),
+        },
+        Err(_) => Ok(config.birthdate),
+    }
+}
+
+/// Age as full years and the days since the last birthday.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Age {
+    pub years: i32,
+    pub days: i64,
+    pub total_days: i64,
+}
+
+impl Age {
+    /// Returns the age on `today` of someone born on `birthdate`,
+    /// or `None` if they are not born yet.
+    pub fn new(birthdate: NaiveDate, today: NaiveDate) -> Option<Self> {
+        if birthdate > today {
+            return None;
+        }
+
+        let mut last_birthday = yearly_date(birthdate, today.year())?;
+        if last_birthday > today {
+            last_birthday = yearly_date(birthdate, last_birthday.year() - 1)?;
+        }
+
+        Some(Age {
+            years: last_birthday.year() - birthdate.year(),
+            days: today.signed_duration_since(last_birthday).num_days(),
+            total_days: today.signed_duration_since(birthdate).num_days(),
+        })
+    }
+
+    pub fn describe(&self, locale: Locale) -> String {
+        locale.text(Message::Age { years: self.years, days: self.days })
+    }
+
+    /// Returns true if today is the birthday.
+    pub fn is_birthday(&self) -> bool {
+        self.days == 0
+    }
+}
+
+impl fmt::Display for Age {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.describe(Locale::English))
+    }
+}
+
+/// A person's birthday, either from the events file or from `BIRTHDATE`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Birthday {
+    pub name: String,
+    pub date: NaiveDate,
+}
+
+impl Birthday {
+    pub fn is_own(&self) -> bool {
+        self.name.eq_ignore_ascii_case(OWN_NAME)
+    }
+
+    /// Returns the date of the next birthday on or after `today`.
+    pub fn next(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let this_year = yearly_date(self.date, today.year())?;
+        if this_year >= today {
+            Some(this_year)
+        }
+        else {
+            yearly_date(self.date, this_year.year() + 1)
+        }
+    }
+
+    /// Describes the age of the person and the time until the next birthday.
+    pub fn report(&self, today: NaiveDate, locale: Locale) -> String {
+        let name = self.name.as_str();
+        let age = match Age::new(self.date, today) {
+            Some(age) => age,
+            None => {
+                let days = self.date.signed_duration_since(today).num_days();
+                return locale.text(Message::WillBeBorn { name, days });
+            }
+        };
+        let description = age.describe(locale);
+
+        let mut report = String::new();
+        if self.is_own() {
+            if age.is_birthday() {
+                report.push_str(&locale.text(Message::HappyBirthday));
+            }
+            report.push_str(&locale.text(Message::YouAre { total_days: age.total_days, age: &description }));
+        }
+        else {
+            if age.is_birthday() {
+                report.push_str(&locale.text(Message::HappyBirthdayTo { name, years: age.years, total_days: age.total_days }));
+            }
+            else {
+                report.push_str(&locale.text(Message::IsOld { name, total_days: age.total_days, age: &description }));
+            }
+        }
+
+        if !age.is_birthday() {
+            if let Some(next) = self.next(today) {
+                let days = next.signed_duration_since(today).num_days();
+                report.push_str(&locale.text(Message::Turning { years: age.years + 1, days }));
+            }
+        }
+        report
+    }
+}
+
+/// Collects the birthdays in the events, with the user's own birthday first.
+/// The `own_birthdate` is used if there is no birthday event for the user.
+pub fn from_events(events: &[&Event], own_birthdate: Option<NaiveDate>) -> Vec<Birthday> {
+    let mut birthdays: Vec<Birthday> = events
+        .iter()
+        .filter(|event| event.kind() == EventKind::Birthday)
+        .map(|event| Birthday { name: event.description.clone(), date: event.date })
+        .collect();
+
+    if !birthdays.iter().any(|birthday| birthday.is_own()) {
+        if let Some(date) = own_birthdate {
+            birthdays.push(Birthday { name: OWN_NAME.to_string(), date });
+        }
+    }
+
+    birthdays.sort_by_key(|birthday| !birthday.is_own());
+    birthdays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn age(birthdate: NaiveDate, today: NaiveDate) -> (i32, i64) {
+        let age = Age::new(birthdate, today).unwrap();
+        (age.years, age.days)
+    }
+
+    #[test]
+    fn counts_the_age_of_a_leap_day_birthday() {
+        let birthdate = date(2000, 2, 29);
+        assert_eq!(age(birthdate, date(2023, 2, 27)), (22, 364));
+        assert_eq!(age(birthdate, date(2023, 2, 28)), (23, 0));
+        assert_eq!(age(birthdate, date(2024, 2, 28)), (23, 365));
+        assert_eq!(age(birthdate, date(2024, 2, 29)), (24, 0));
+
+        let birthday = Birthday { name: "Leap".to_string(), date: birthdate };
+        assert_eq!(birthday.next(date(2022, 3, 1)), Some(date(2023, 2, 28)));
+        assert_eq!(birthday.next(date(2023, 3, 1)), Some(date(2024, 2, 29)));
+    }
+
+    #[test]
+    fn finds_the_birthday_before_and_after_it_in_the_year() {
+        let birthday = Birthday { name: "June".to_string(), date: date(1990, 6, 15) };
+
+        assert_eq!(age(birthday.date, date(2024, 6, 14)), (33, 365));
+        assert_eq!(birthday.next(date(2024, 6, 14)), Some(date(2024, 6, 15)));
+
+        let today = date(2024, 6, 15);
+        assert!(Age::new(birthday.date, today).unwrap().is_birthday());
+        assert_eq!(age(birthday.date, today), (34, 0));
+        assert_eq!(birthday.next(today), Some(today));
+
+        assert_eq!(age(birthday.date, date(2024, 6, 16)), (34, 1));
+        assert_eq!(birthday.next(date(2024, 6, 16)), Some(date(2025, 6, 15)));
+    }
+
+    #[test]
+    fn has_no_age_before_birth() {
+        let birthday = Birthday { name: "Baby".to_string(), date: date(2025, 1, 1) };
+        let today = date(2024, 12, 31);
+        assert_eq!(Age::new(birthday.date, today), None);
+        assert_eq!(age(birthday.date, birthday.date), (0, 0));
+        assert_eq!(birthday.next(today), Some(birthday.date));
+        let expected = Locale::English.text(Message::WillBeBorn { name: "Baby", days: 1 });
+        assert_eq!(birthday.report(today, Locale::English), expected);
+    }
+}
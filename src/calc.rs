@@ -0,0 +1,109 @@
+use chrono::{NaiveDate, DateTime, Datelike, Duration, Local, Months};
+
+/// Where the reference date for day counts comes from. Everything that
+/// depends on "today" takes the date from a clock, so that it can be
+/// set to any date for planning and testing.
+pub trait Clock {
+    fn today(&self) -> NaiveDate;
+}
+
+/// The real date in the local time zone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        let now: DateTime<Local> = Local::now();
+        now.date_naive()
+    }
+}
+
+/// A clock that is stopped on the given date.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub NaiveDate);
+
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
+
+/// More days than there are between the first and the last date
+/// chrono can represent, but few enough for a `Duration`.
+const MAX_DAYS: i64 = 1_000_000_000;
+
+/// Returns the date the given number of days after `date`, or before
+/// it if the count is negative, or `None` if that is out of range.
+pub fn add_days(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    if !(-MAX_DAYS..=MAX_DAYS).contains(&days) {
+        return None;
+    }
+    date.checked_add_signed(Duration::days(days))
+}
+
+/// Returns the signed number of days from `from` to `to`:
+/// positive if `to` is later, negative if it is earlier.
+pub fn days_between(from: NaiveDate, to: NaiveDate) -> i64 {
+    to.signed_duration_since(from).num_days()
+}
+
+/// The distance between two dates in whole calendar months and the days
+/// left over, whichever date is earlier. A month from January 31 is
+/// the end of February, like in `Span::add_to`.
+pub fn months_and_days(from: NaiveDate, to: NaiveDate) -> (u32, i64) {
+    let (first, last) = if from <= to { (from, to) } else { (to, from) };
+    let add = |months: u32| first.checked_add_months(Months::new(months));
+
+    let estimate = (last.year() - first.year()) * 12 + last.month() as i32 - first.month() as i32;
+    let mut months = estimate.max(0) as u32;
+    while months > 0 && add(months).is_none_or(|date| date > last) {
+        months -= 1;
+    }
+    let start = add(months).unwrap_or(first);
+    (months, days_between(start, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn counts_months_and_days() {
+        assert_eq!(months_and_days(date(2024, 1, 15), date(2024, 1, 15)), (0, 0));
+        assert_eq!(months_and_days(date(2024, 1, 15), date(2024, 3, 20)), (2, 5));
+        assert_eq!(months_and_days(date(2024, 1, 15), date(2024, 3, 10)), (1, 24));
+        assert_eq!(months_and_days(date(2024, 1, 15), date(2025, 1, 15)), (12, 0));
+    }
+
+    #[test]
+    fn months_and_days_go_either_way() {
+        assert_eq!(months_and_days(date(2024, 3, 20), date(2024, 1, 15)), (2, 5));
+    }
+
+    #[test]
+    fn a_month_from_the_end_of_a_month_is_the_end_of_a_shorter_one() {
+        assert_eq!(months_and_days(date(2024, 1, 31), date(2024, 2, 29)), (1, 0));
+        assert_eq!(months_and_days(date(2023, 1, 31), date(2023, 3, 1)), (1, 1));
+        assert_eq!(months_and_days(date(2024, 1, 31), date(2024, 3, 31)), (2, 0));
+    }
+
+    #[test]
+    fn months_and_days_across_the_whole_range() {
+        let (months, _) = months_and_days(NaiveDate::MIN, NaiveDate::MAX);
+        assert!(months > 12 * 500_000);
+    }
+
+    #[test]
+    fn adds_days_within_range() {
+        assert_eq!(add_days(date(2024, 2, 28), 2), Some(date(2024, 3, 1)));
+        assert_eq!(add_days(date(2024, 3, 1), -2), Some(date(2024, 2, 28)));
+        assert_eq!(add_days(NaiveDate::MAX, 1), None);
+        assert_eq!(add_days(NaiveDate::MIN, -1), None);
+        assert_eq!(add_days(date(2024, 1, 1), i64::MAX), None);
+        assert_eq!(add_days(date(2024, 1, 1), i64::MIN), None);
+    }
+}
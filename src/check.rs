@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use crate::DaysError;
+use crate::store::open_store;
+
+/// Something wrong with one row of an events file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ProblemKind {
+    /// The row has too few or too many columns.
+    ColumnCount { min: usize, max: usize, found: usize },
+    /// The header has a column that `days` does not know, and which
+    /// would be lost if the file were written back.
+    UnknownColumn(String),
+    /// Like an unknown column, but a field in a JSON or TOML file.
+    UnknownField(String),
+    InvalidDate(String),
+    InvalidRecurrence(String),
+    /// The row could not be read at all, like when it is not valid UTF-8.
+    Malformed(String),
+    /// The same event is already on the given line (or entry).
+    Duplicate(u64),
+    EmptyDescription,
+    UnknownCategory(String),
+}
+
+impl ProblemKind {
+    /// Returns true if the row cannot be turned into an event at all.
+    /// The other problems are worth fixing, but the event is still usable.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self,
+            ProblemKind::ColumnCount { .. }
+            | ProblemKind::InvalidDate(_)
+            | ProblemKind::InvalidRecurrence(_)
+            | ProblemKind::Malformed(_))
+    }
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ProblemKind::ColumnCount { min, max, found } if min == max => {
+                write!(f, "expected {} columns, found {}", max, found)
+            },
+            ProblemKind::ColumnCount { min, max, found } if min + 1 == *max => {
+                write!(f, "expected {} or {} columns, found {}", min, max, found)
+            },
+            ProblemKind::ColumnCount { min, max, found } => {
+                write!(f, "expected {} to {} columns, found {}", min, max, found)
+            },
+            ProblemKind::UnknownColumn(name) => {
+                write!(f, "unknown column '{}'", name)
+            },
+            ProblemKind::UnknownField(name) => {
+                write!(f, "unknown field '{}'", name)
+            },
+            ProblemKind::InvalidDate(value) => {
+                write!(f, "invalid date '{}', expected YYYY-MM-DD", value)
+            },
+            ProblemKind::InvalidRecurrence(message) => {
+                write!(f, "{}", message)
+            },
+            ProblemKind::Malformed(message) => {
+                write!(f, "{}", message)
+            },
+            ProblemKind::Duplicate(line) => {
+                write!(f, "duplicate of the event on line {}", line)
+            },
+            ProblemKind::EmptyDescription => {
+                write!(f, "empty description")
+            },
+            ProblemKind::UnknownCategory(category) => {
+                write!(f, "unknown category '{}'", category)
+            },
+        }
+    }
+}
+
+/// A problem on a line of an events file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Problem {
+    pub line: u64,
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+/// Validates a whole events file and returns all the problems in it.
+/// If `categories` is not empty, events in other categories are reported.
+/// Errors are returned only if the file cannot be read at all.
+pub fn check_events(path: &Path, categories: &[String]) -> Result<Vec<Problem>, DaysError> {
+    let mut problems: Vec<Problem> = Vec::new();
+    let mut seen: HashMap<String, u64> = HashMap::new();
+
+    for entry in open_store(path)?.read()?.into_iter() {
+        let line = entry.position;
+        let event = match entry.event {
+            Ok(event) => event,
+            Err(kind) => {
+                problems.push(Problem { line, kind });
+                continue;
+            }
+        };
+
+        if event.description.trim().is_empty() {
+            problems.push(Problem { line, kind: ProblemKind::EmptyDescription });
+        }
+
+        if !categories.is_empty()
+            && !categories.iter().any(|category| category.eq_ignore_ascii_case(&event.category)) {
+            problems.push(Problem { line, kind: ProblemKind::UnknownCategory(event.category.clone()) });
+        }
+
+        match seen.get(&event.id()) {
+            Some(first_line) => {
+                problems.push(Problem { line, kind: ProblemKind::Duplicate(*first_line) });
+            },
+            None => {
+                seen.insert(event.id(), line);
+            }
+        }
+    }
+
+    Ok(problems)
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use chrono::{NaiveDate, Weekday};
+use chrono::format::{StrftimeItems, Item};
+use serde::Deserialize;
+use crate::backup::DEFAULT_BACKUPS;
+use crate::locale::Locale;
+use crate::lock::DEFAULT_LOCK_TIMEOUT;
+use crate::milestone::{self, Rule};
+use crate::natural::DateOrder;
+use crate::store::Format;
+use crate::workdays::DEFAULT_WEEKEND;
+
+/// Name of the configuration file in the days directory.
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The configuration file as it is written, before validation.
+/// All the settings are optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    categories: Option<Vec<String>>,
+    date_format: Option<String>,
+    locale: Option<String>,
+    date_order: Option<String>,
+    birthdate: Option<String>,
+    window: Option<u32>,
+    color: Option<bool>,
+    week_start: Option<String>,
+    milestones: Option<Vec<String>>,
+    backups: Option<usize>,
+    lock_timeout: Option<u64>,
+    format: Option<String>,
+    business_days: Option<bool>,
+    weekend: Option<Vec<String>>,
+    holidays: Option<PathBuf>,
+    holiday_category: Option<String>,
+}
+
+/// Validated settings, with defaults for anything not in the file.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Known event categories. Adding an event in some other
+    /// category prints a warning. Empty means anything goes.
+    pub categories: Vec<String>,
+    /// `strftime`-style format for showing dates,
+    /// or `None` for the default of the language.
+    pub date_format: Option<String>,
+    /// Language of the output, if not the one from the environment.
+    pub locale: Option<Locale>,
+    /// How to read dates like `12/24` when adding events.
+    pub date_order: DateOrder,
+    /// The user's own birthdate, if not given otherwise.
+    pub birthdate: Option<NaiveDate>,
+    /// Only list events within this many days from today.
+    pub window: Option<u32>,
+    /// Use colors in the output when it goes to a terminal.
+    pub color: bool,
+    pub week_start: Weekday,
+    pub milestones: Vec<Rule>,
+    /// How many backups of each events file to keep. 0 turns backups off.
+    pub backups: usize,
+    /// How many seconds to wait for another process to finish
+    /// changing an events file.
+    pub lock_timeout: u64,
+    /// Format of the events file in the days directory. Other files
+    /// are read in the format their extension says.
+    pub format: Format,
+    /// Count business days instead of calendar days.
+    pub business_days: bool,
+    /// Days of the week that are not business days.
+    pub weekend: Vec<Weekday>,
+    /// File with one holiday date per line. A relative path is relative
+    /// to the directory of the configuration file.
+    pub holidays: Option<PathBuf>,
+    /// Events in this category are holidays too.
+    pub holiday_category: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            categories: Vec::new(),
+            date_format: None,
+            locale: None,
+            date_order: DateOrder::default(),
+            birthdate: None,
+            window: None,
+            color: true,
+            week_start: Weekday::Mon,
+            milestones: milestone::DEFAULT_RULES.to_vec(),
+            backups: DEFAULT_BACKUPS,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            format: Format::default(),
+            business_days: false,
+            weekend: DEFAULT_WEEKEND.to_vec(),
+            holidays: None,
+            holiday_category: None,
+        }
+    }
+}
+
+/// A problem with the configuration file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigError(String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError { }
+
+impl Config {
+    /// Reads the configuration from the file, or returns the defaults
+    /// if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| ConfigError(format!("{}: {}", path.display(), err)))?;
+        let mut config = Config::parse(&text)
+            .map_err(|err| ConfigError(format!("{}: {}", path.display(), err)))?;
+        if let (Some(holidays), Some(dir)) = (&config.holidays, path.parent()) {
+            config.holidays = Some(dir.join(holidays));
+        }
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let file: ConfigFile = toml::from_str(text)
+            .map_err(|err| ConfigError(err.message().to_string()))?;
+
+        let mut config = Config::default();
+
+        if let Some(categories) = file.categories {
+            config.categories = categories;
+        }
+
+        if let Some(date_format) = file.date_format {
+            if StrftimeItems::new(&date_format).any(|item| item == Item::Error) {
+                return Err(ConfigError(format!("invalid date_format '{}'", date_format)));
+            }
+            config.date_format = Some(date_format);
+        }
+
+        if let Some(locale) = file.locale {
+            config.locale = Some(locale.parse::<Locale>().map_err(|err| ConfigError(err.to_string()))?);
+        }
+
+        if let Some(date_order) = file.date_order {
+            config.date_order = date_order.parse::<DateOrder>().map_err(|err| ConfigError(err.to_string()))?;
+        }
+
+        if let Some(birthdate) = file.birthdate {
+            match NaiveDate::parse_from_str(&birthdate, "%Y-%m-%d") {
+                Ok(date) => config.birthdate = Some(date),
+                Err(_) => {
+                    return Err(ConfigError(format!("invalid birthdate '{}', expected YYYY-MM-DD", birthdate)));
+                }
+            }
+        }
+
+        config.window = file.window;
+
+        if let Some(color) = file.color {
+            config.color = color;
+        }
+
+        if let Some(week_start) = file.week_start {
+            match week_start.parse::<Weekday>() {
+                Ok(weekday) => config.week_start = weekday,
+                Err(_) => {
+                    return Err(ConfigError(format!("invalid week_start '{}', expected a weekday", week_start)));
+                }
+            }
+        }
+
+        if let Some(milestones) = file.milestones {
+            let mut rules: Vec<Rule> = Vec::new();
+            for value in milestones.iter() {
+                rules.push(value.parse::<Rule>().map_err(|err| ConfigError(err.to_string()))?);
+            }
+            config.milestones = rules;
+        }
+
+        if let Some(backups) = file.backups {
+            config.backups = backups;
+        }
+
+        if let Some(lock_timeout) = file.lock_timeout {
+            config.lock_timeout = lock_timeout;
+        }
+
+        if let Some(business_days) = file.business_days {
+            config.business_days = business_days;
+        }
+
+        if let Some(weekend) = file.weekend {
+            let mut weekdays: Vec<Weekday> = Vec::new();
+            for value in weekend.iter() {
+                match value.parse::<Weekday>() {
+                    Ok(weekday) => weekdays.push(weekday),
+                    Err(_) => {
+                        return Err(ConfigError(format!("invalid weekend day '{}', expected a weekday", value)));
+                    }
+                }
+            }
+            weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+            weekdays.dedup();
+            if weekdays.len() == 7 {
+                return Err(ConfigError("the weekend can't be the whole week".to_string()));
+            }
+            config.weekend = weekdays;
+        }
+
+        config.holidays = file.holidays;
+        config.holiday_category = file.holiday_category;
+
+        if let Some(format) = file.format {
+            config.format = format.parse::<Format>().map_err(|err| ConfigError(err.to_string()))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the format for showing dates in the language.
+    pub fn date_format(&self, locale: Locale) -> &str {
+        self.date_format.as_deref().unwrap_or(locale.date_format())
+    }
+
+    /// Returns true if the category is one of the configured ones,
+    /// or if no categories are configured.
+    pub fn is_known_category(&self, category: &str) -> bool {
+        self.categories.is_empty()
+            || self.categories.iter().any(|known| known.eq_ignore_ascii_case(category))
+    }
+}
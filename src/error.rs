@@ -0,0 +1,228 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::check::ProblemKind;
+use crate::config::ConfigError;
+use crate::milestone::ParseRuleError;
+use crate::natural::ParseDateError;
+use crate::recurrence::ParseRecurrenceError;
+use crate::span::ParseSpanError;
+use crate::store::Format;
+
+/// Errors from reading, writing and interpreting events.
+#[derive(Debug)]
+pub enum DaysError {
+    HomeDirectoryNotFound,
+    /// The days directory exists, but it is not a directory.
+    WorkingDirectoryNotFound(PathBuf),
+    CreateError { path: PathBuf, source: io::Error },
+    WriteError { path: PathBuf, source: io::Error },
+    ReadError { path: PathBuf, source: io::Error },
+    /// A backup could not be made or removed.
+    BackupError { path: PathBuf, source: io::Error },
+    BackupNotFound(String),
+    /// The lock file for an events file could not be created or locked.
+    LockError { path: PathBuf, source: io::Error },
+    /// Another process held the lock on an events file for too long.
+    LockTimeout { path: PathBuf, timeout: Duration },
+    /// A malformed events file. The line is the line number in the file, if known.
+    CsvError { path: PathBuf, line: Option<u64>, source: csv::Error },
+    /// A malformed JSON, TOML or SQLite events file.
+    FormatError { path: PathBuf, message: String },
+    /// The format is not supported by this build.
+    UnsupportedFormat(Format),
+    /// A row in an events file that is not a valid event, in strict mode.
+    InvalidEvent { path: PathBuf, line: u64, problem: ProblemKind },
+    /// The events file has columns or fields that `days` does not know,
+    /// which would be lost if it were written.
+    UnknownColumns { path: PathBuf, columns: Vec<String> },
+    /// `days check` found problems in the events files.
+    ProblemsFound(usize),
+    InvalidDate(String),
+    /// A date given in words that could not be made sense of.
+    InvalidDateInput(ParseDateError),
+    InvalidRecurrence(ParseRecurrenceError),
+    InvalidMilestoneRule(ParseRuleError),
+    InvalidConfig(ConfigError),
+    InvalidSpan(ParseSpanError),
+    /// A span of time too long to add to a date.
+    SpanOutOfRange(String),
+    InvalidPattern(regex::Error),
+    EventNotFound(String),
+    /// The holidays file could not be read.
+    HolidaysReadError { path: PathBuf, source: io::Error },
+    /// A line in the holidays file that is not a date.
+    InvalidHoliday { path: PathBuf, line: u64, value: String },
+    /// The listing could not be turned into JSON.
+    OutputError(serde_json::Error),
+    AmbiguousId { prefix: String, matches: Vec<String> },
+}
+
+impl DaysError {
+    /// Returns the process exit code for the error, following the
+    /// conventions of `sysexits.h`.
+    pub fn exit_code(&self) -> exitcode::ExitCode {
+        match self {
+            DaysError::HomeDirectoryNotFound => exitcode::CONFIG,
+            DaysError::WorkingDirectoryNotFound(_) => exitcode::CANTCREAT,
+            DaysError::CreateError { .. } => exitcode::CANTCREAT,
+            DaysError::WriteError { source, .. } => match source.kind() {
+                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => exitcode::CANTCREAT,
+                _ => exitcode::IOERR,
+            },
+            DaysError::ReadError { source, .. } => match source.kind() {
+                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => exitcode::NOINPUT,
+                _ => exitcode::IOERR,
+            },
+            DaysError::BackupError { .. } => exitcode::IOERR,
+            DaysError::BackupNotFound(_) => exitcode::NOINPUT,
+            DaysError::LockError { .. } => exitcode::IOERR,
+            DaysError::LockTimeout { .. } => exitcode::TEMPFAIL,
+            DaysError::CsvError { .. } => exitcode::DATAERR,
+            DaysError::FormatError { .. } => exitcode::DATAERR,
+            DaysError::UnsupportedFormat(_) => exitcode::UNAVAILABLE,
+            DaysError::InvalidEvent { .. } => exitcode::DATAERR,
+            DaysError::UnknownColumns { .. } => exitcode::DATAERR,
+            DaysError::ProblemsFound(_) => exitcode::DATAERR,
+            DaysError::InvalidConfig(_) => exitcode::CONFIG,
+            DaysError::EventNotFound(_) => exitcode::DATAERR,
+            DaysError::HolidaysReadError { source, .. } => match source.kind() {
+                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => exitcode::NOINPUT,
+                _ => exitcode::IOERR,
+            },
+            DaysError::InvalidHoliday { .. } => exitcode::DATAERR,
+            DaysError::OutputError(_) => exitcode::SOFTWARE,
+            DaysError::AmbiguousId { .. } => exitcode::DATAERR,
+            DaysError::InvalidDate(_)
+            | DaysError::InvalidDateInput(_)
+            | DaysError::InvalidRecurrence(_)
+            | DaysError::InvalidMilestoneRule(_)
+            | DaysError::InvalidSpan(_)
+            | DaysError::SpanOutOfRange(_)
+            | DaysError::InvalidPattern(_) => exitcode::USAGE,
+        }
+    }
+}
+
+impl fmt::Display for DaysError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            DaysError::HomeDirectoryNotFound => {
+                write!(f, "Home directory not found")
+            },
+            DaysError::WorkingDirectoryNotFound(path) => {
+                write!(f, "{} is not a directory", path.display())
+            },
+            DaysError::ReadError { path, source } => {
+                write!(f, "Error reading events from {}: {}", path.display(), source)
+            },
+            DaysError::WriteError { path, source } => {
+                write!(f, "Error writing events to {}: {}", path.display(), source)
+            },
+            DaysError::CreateError { path, source } => {
+                write!(f, "Unable to create working directory {}: {}", path.display(), source)
+            },
+            DaysError::BackupError { path, source } => {
+                write!(f, "Error with backup {}: {}", path.display(), source)
+            },
+            DaysError::BackupNotFound(name) => {
+                write!(f, "No backup matches '{}'", name)
+            },
+            DaysError::LockError { path, source } => {
+                write!(f, "Unable to lock {}: {}", path.display(), source)
+            },
+            DaysError::LockTimeout { path, timeout } => {
+                let unit = if timeout.as_secs() == 1 { "second" } else { "seconds" };
+                write!(f, "{} is being changed by another process; gave up after waiting {} {}",
+                    path.display(), timeout.as_secs(), unit)
+            },
+            DaysError::CsvError { path, line: Some(line), source } => {
+                write!(f, "{}:{}: {}", path.display(), line, source)
+            },
+            DaysError::CsvError { path, line: None, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            },
+            DaysError::FormatError { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            },
+            DaysError::UnsupportedFormat(format) => {
+                write!(f, "This build of days does not support the {} format", format)
+            },
+            DaysError::InvalidEvent { path, line, problem } => {
+                write!(f, "{}:{}: {}", path.display(), line, problem)
+            },
+            DaysError::UnknownColumns { path, columns } => {
+                write!(f, "{} can't be changed by days, because it would lose the columns \
+                    or fields it does not know: {}", path.display(), columns.join(", "))
+            },
+            DaysError::ProblemsFound(1) => {
+                write!(f, "Found 1 problem")
+            },
+            DaysError::ProblemsFound(count) => {
+                write!(f, "Found {} problems", count)
+            },
+            DaysError::InvalidDate(value) => {
+                write!(f, "Invalid date '{}', expected YYYY-MM-DD", value)
+            },
+            DaysError::InvalidDateInput(err) => {
+                write!(f, "{}", err)
+            },
+            DaysError::InvalidRecurrence(err) => {
+                write!(f, "{}", err)
+            },
+            DaysError::InvalidMilestoneRule(err) => {
+                write!(f, "{}", err)
+            },
+            DaysError::InvalidConfig(err) => {
+                write!(f, "Error in configuration file {}", err)
+            },
+            DaysError::InvalidSpan(err) => {
+                write!(f, "{}", err)
+            },
+            DaysError::SpanOutOfRange(span) => {
+                write!(f, "The span {} is too long", span)
+            },
+            DaysError::InvalidPattern(err) => {
+                write!(f, "Invalid regular expression: {}", err)
+            },
+            DaysError::OutputError(err) => {
+                write!(f, "Error producing JSON output: {}", err)
+            },
+            DaysError::HolidaysReadError { path, source } => {
+                write!(f, "Error reading holidays from {}: {}", path.display(), source)
+            },
+            DaysError::InvalidHoliday { path, line, value } => {
+                write!(f, "{}:{}: invalid date '{}', expected YYYY-MM-DD", path.display(), line, value)
+            },
+            DaysError::EventNotFound(prefix) => {
+                write!(f, "No event matches '{}'", prefix)
+            },
+            DaysError::AmbiguousId { prefix, matches } => {
+                write!(f, "'{}' matches more than one event: {}", prefix, matches.join(", "))
+            },
+        }
+    }
+}
+
+impl std::error::Error for DaysError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DaysError::CreateError { source, .. } => Some(source),
+            DaysError::WriteError { source, .. } => Some(source),
+            DaysError::ReadError { source, .. } => Some(source),
+            DaysError::HolidaysReadError { source, .. } => Some(source),
+            DaysError::BackupError { source, .. } => Some(source),
+            DaysError::LockError { source, .. } => Some(source),
+            DaysError::CsvError { source, .. } => Some(source),
+            DaysError::InvalidDateInput(err) => Some(err),
+            DaysError::InvalidRecurrence(err) => Some(err),
+            DaysError::InvalidMilestoneRule(err) => Some(err),
+            DaysError::InvalidConfig(err) => Some(err),
+            DaysError::InvalidSpan(err) => Some(err),
+            DaysError::InvalidPattern(err) => Some(err),
+            DaysError::OutputError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use chrono::{NaiveDate, Datelike};
+use crate::birthday::BIRTHDAY_CATEGORY;
+use crate::calc::days_between;
+use crate::config::DEFAULT_DATE_FORMAT;
+use crate::locale::{Locale, Message, Unit};
+use crate::recurrence::{Recurrence, Frequency};
+
+/// An event read from the events file.
+#[derive(Debug, Clone, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Event {
+    pub date: NaiveDate,
+    pub category: String,
+    pub description: String,
+    pub recurrence: Option<Recurrence>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.describe(DEFAULT_DATE_FORMAT, Locale::English))
+    }
+}
+
+/// The kind of an event determines how it is presented.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EventKind {
+    Regular,
+    Birthday,
+}
+
+impl Event {
+    /// Describes the event with the date in the given `strftime` format.
+    pub fn describe(&self, date_format: &str, locale: Locale) -> String {
+        let mut text = format!("{}: {} ({})", locale.format_date(self.date, date_format), self.description, self.category);
+        if let Some(recurrence) = &self.recurrence {
+            text.push_str(&format!(" [{}]", recurrence));
+        }
+        text
+    }
+
+    pub fn kind(&self) -> EventKind {
+        if self.category.eq_ignore_ascii_case(BIRTHDAY_CATEGORY) {
+            EventKind::Birthday
+        }
+        else {
+            EventKind::Regular
+        }
+    }
+
+    /// Returns the recurrence rule of the event.
+    /// Birthdays repeat yearly unless the file says otherwise.
+    pub fn effective_recurrence(&self) -> Option<Recurrence> {
+        match (self.recurrence, self.kind()) {
+            (None, EventKind::Birthday) => Some(Recurrence { frequency: Frequency::Yearly, until: None }),
+            (recurrence, _) => recurrence,
+        }
+    }
+
+    /// Returns a short identifier derived from the date, category and
+    /// description of the event. The identifier is stable across runs
+    /// (it uses FNV-1a instead of the standard library hasher, whose
+    /// output may change between Rust releases).
+    pub fn id(&self) -> String {
+        let key = format!("{}\u{1f}{}\u{1f}{}", self.date, self.category, self.description);
+        format!("{:08x}", fnv1a(key.as_bytes()))
+    }
+}
+
+/// The 32-bit FNV-1a hash, which unlike the standard library hasher
+/// stays the same between Rust releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u32 {
+    const FNV_OFFSET_BASIS: u32 = 0x811c9dc5;
+    const FNV_PRIME: u32 = 0x01000193;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in bytes.iter() {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// An event together with its signed day offset from a reference date.
+/// Positive offsets are in the future, negative ones in the past.
+/// For a recurring event the offset is to the next occurrence,
+/// unless the recurrence has already ended, while `since` always
+/// counts the days from the original date.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct EventItem {
+    pub days: i64,
+    pub next: Option<NaiveDate>,
+    pub since: i64,
+    pub event: Event,
+    /// Name of the file the event came from, when listing several files.
+    pub source: Option<String>,
+    /// The offset in business days, when counting those.
+    pub business_days: Option<i64>,
+}
+
+impl EventItem {
+    pub fn new(event: Event, today: NaiveDate) -> Self {
+        let next = event.effective_recurrence()
+            .and_then(|recurrence| recurrence.next_occurrence(event.date, today));
+        let days = days_between(today, next.unwrap_or(event.date));
+        let since = days_between(event.date, today);
+        EventItem { days, next, since, event, source: None, business_days: None }
+    }
+
+    /// Returns the distance to the event without its direction,
+    /// in business days when counting those.
+    fn distance(&self) -> (i64, Unit) {
+        match self.business_days {
+            Some(days) => (days.abs(), Unit::BusinessDay),
+            None => (self.days.abs(), Unit::Day),
+        }
+    }
+
+    pub fn relative_description(&self, locale: Locale) -> String {
+        let (count, unit) = self.distance();
+        match self.days {
+            0 => locale.text(Message::Today),
+            days if days > 0 => locale.text(Message::In(count, unit)),
+            _ => locale.text(Message::Ago(count, unit)),
+        }
+    }
+
+    fn birthday_description(&self, next: NaiveDate, locale: Locale) -> String {
+        let years = next.year() - self.event.date.year();
+        let (days, unit) = self.distance();
+        match self.days {
+            0 => locale.text(Message::BirthdayToday { years }),
+            _ => locale.text(Message::TurnsIn { years, days, unit }),
+        }
+    }
+
+    /// Describes the event and its day offset, with the dates
+    /// in the given `strftime` format.
+    pub fn describe(&self, date_format: &str, locale: Locale) -> String {
+        let mut text = String::new();
+        if let Some(source) = &self.source {
+            text.push_str(&format!("[{}] ", source));
+        }
+        text.push_str(&format!("{}  {} - ", self.event.id(), self.event.describe(date_format, locale)));
+
+        if let (EventKind::Birthday, Some(next)) = (self.event.kind(), self.next) {
+            if self.since > 0 {
+                text.push_str(&self.birthday_description(next, locale));
+                return text;
+            }
+        }
+
+        text.push_str(&self.relative_description(locale));
+        if let Some(next) = self.next {
+            if next != self.event.date {
+                let date = locale.format_date(next, date_format);
+                text.push_str(&locale.text(Message::NextOn { date: &date, since: self.since }));
+            }
+        }
+        text
+    }
+}
+
+impl fmt::Display for EventItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.describe(DEFAULT_DATE_FORMAT, Locale::English))
+    }
+}
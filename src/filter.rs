@@ -0,0 +1,181 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use crate::{Event, EventItem, DaysError};
+use crate::span::Span;
+
+/// Criteria for choosing which events to list.
+/// An empty filter lets every event through.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Only these categories, if any are given.
+    pub categories: Vec<String>,
+    /// Never these categories.
+    pub excluded_categories: Vec<String>,
+    /// Only events on or after this date.
+    pub since: Option<NaiveDate>,
+    /// Only events on or before this date.
+    pub until: Option<NaiveDate>,
+    /// Only events in the past (before today).
+    pub past: bool,
+    /// Only events today or in the future.
+    pub future: bool,
+    /// Only events with this text in the description, ignoring case.
+    pub search: Option<String>,
+    /// Only events with a description matching this regular expression.
+    pub regex: Option<Regex>,
+}
+
+impl Filter {
+    /// Narrows the dates to those within the span from today, in either
+    /// direction, keeping any `since` and `until` that are narrower still.
+    pub fn limit_to(&mut self, within: Span, today: NaiveDate) -> Result<(), DaysError> {
+        let before = within.negated().and_then(|negated| negated.add_to(today));
+        let (start, end) = match (before, within.add_to(today)) {
+            (Some(start), Some(end)) => (start.min(end), start.max(end)),
+            _ => {
+                return Err(DaysError::SpanOutOfRange(within.to_string()));
+            }
+        };
+        self.since = Some(self.since.map_or(start, |since| since.max(start)));
+        self.until = Some(self.until.map_or(end, |until| until.min(end)));
+        Ok(())
+    }
+
+    /// Returns true if the event passes the filter. The `date` is the one
+    /// the event is listed with (the next occurrence of a recurring event),
+    /// and `days` is the offset of that date from today.
+    pub fn matches(&self, event: &Event, date: NaiveDate, days: i64) -> bool {
+        let in_category = |categories: &[String]| {
+            categories.iter().any(|category| category.eq_ignore_ascii_case(&event.category))
+        };
+
+        if !self.categories.is_empty() && !in_category(&self.categories) {
+            return false;
+        }
+        if in_category(&self.excluded_categories) {
+            return false;
+        }
+
+        if self.since.is_some_and(|since| date < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+
+        if self.past && days >= 0 {
+            return false;
+        }
+        if self.future && days < 0 {
+            return false;
+        }
+
+        if let Some(search) = &self.search {
+            if !event.description.to_lowercase().contains(&search.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(&event.description) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// The orders the listed events can be sorted in.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SortOrder {
+    /// Soonest upcoming first, then the most recent past events.
+    #[default]
+    Upcoming,
+    /// By the listed date (the next occurrence for recurring events).
+    Date,
+    /// By distance from today in either direction, nearest first.
+    Distance,
+    /// By category, then by date.
+    Category,
+    /// By description, then by date.
+    Description,
+}
+
+/// Sorts the items. Ties are broken by the natural order of the items,
+/// so that the output is the same on every run.
+pub fn sort_items(items: &mut [EventItem], order: SortOrder) {
+    match order {
+        SortOrder::Upcoming => {
+            items.sort_by(|a, b| {
+                let key = |item: &EventItem| (item.days < 0, item.days.abs());
+                key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+            });
+        },
+        SortOrder::Date => {
+            items.sort_by(|a, b| {
+                let key = |item: &EventItem| item.next.unwrap_or(item.event.date);
+                key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+            });
+        },
+        SortOrder::Distance => {
+            items.sort_by(|a, b| a.days.abs().cmp(&b.days.abs()).then_with(|| a.cmp(b)));
+        },
+        SortOrder::Category => {
+            items.sort_by(|a, b| {
+                let key = |item: &EventItem| item.event.category.to_lowercase();
+                key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+            });
+        },
+        SortOrder::Description => {
+            items.sort_by(|a, b| {
+                let key = |item: &EventItem| item.event.description.to_lowercase();
+                key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+            });
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn item(event_date: NaiveDate, today: NaiveDate) -> EventItem {
+        let event = Event {
+            date: event_date,
+            category: "work".to_string(),
+            description: "Event".to_string(),
+            recurrence: None,
+        };
+        EventItem::new(event, today)
+    }
+
+    #[test]
+    fn upcoming_events_come_before_past_ones() {
+        let today = date(2024, 3, 1);
+        let mut items: Vec<EventItem> = [date(2024, 2, 29), date(2024, 3, 10), date(2024, 3, 1), date(2023, 1, 1)]
+            .into_iter()
+            .map(|event_date| item(event_date, today))
+            .collect();
+        sort_items(&mut items, SortOrder::Upcoming);
+        let days: Vec<i64> = items.iter().map(|item| item.days).collect();
+        assert_eq!(days, [0, 9, -1, -425]);
+    }
+
+    #[test]
+    fn limits_to_a_window_around_today() {
+        let today = date(2024, 3, 1);
+        let mut filter = Filter { since: Some(date(2024, 2, 20)), ..Filter::default() };
+        filter.limit_to("2w".parse().unwrap(), today).unwrap();
+        assert_eq!(filter.since, Some(date(2024, 2, 20)));
+        assert_eq!(filter.until, Some(date(2024, 3, 15)));
+
+        let mut filter = Filter::default();
+        filter.limit_to("-1m".parse().unwrap(), today).unwrap();
+        assert_eq!((filter.since, filter.until), (Some(date(2024, 2, 1)), Some(date(2024, 4, 1))));
+        assert!(Filter::default().limit_to("99999999y".parse().unwrap(), today).is_err());
+    }
+}
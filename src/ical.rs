@@ -0,0 +1,453 @@
+use chrono::{NaiveDate, NaiveDateTime, Datelike, Weekday, Months};
+use crate::{Event, EventKind};
+use crate::calc::add_days;
+use crate::recurrence::{Frequency, Recurrence, MAX_EVERY_DAYS};
+
+/// Identifies the program that made an exported calendar.
+pub const PRODUCT_ID: &str = "-//coniferprod//days//EN";
+
+/// Lines longer than this many octets are folded, as RFC 5545 requires.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// An exported calendar, with notes about events that calendar
+/// applications will not show exactly like `days` does.
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub text: String,
+    pub notes: Vec<String>,
+}
+
+/// Events read from a calendar, with notes about anything
+/// that could not be carried over.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub events: Vec<Event>,
+    pub notes: Vec<String>,
+}
+
+/// Converts the events to an iCalendar calendar of all-day events.
+/// The `stamp` is the time the calendar is made, in UTC.
+pub fn export(events: &[Event], stamp: NaiveDateTime) -> Export {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let mut notes: Vec<String> = Vec::new();
+
+    for event in events.iter() {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@days", event.id()));
+        lines.push(format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", event.date.succ_opt().unwrap_or(event.date).format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape(&event.description)));
+        lines.push(format!("CATEGORIES:{}", escape(&event.category)));
+        if let Some(recurrence) = event.effective_recurrence() {
+            lines.push(format!("RRULE:{}", to_rrule(&recurrence)));
+            notes.extend(export_notes(event, &recurrence));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut text = String::new();
+    for line in lines.iter() {
+        text.push_str(&fold(line));
+        text.push_str("\r\n");
+    }
+    Export { text, notes }
+}
+
+fn to_rrule(recurrence: &Recurrence) -> String {
+    let mut rule = match recurrence.frequency {
+        Frequency::Yearly => "FREQ=YEARLY".to_string(),
+        Frequency::Monthly(day) => format!("FREQ=MONTHLY;BYMONTHDAY={}", day),
+        Frequency::Weekly(weekday) => format!("FREQ=WEEKLY;BYDAY={}", weekday_code(weekday)),
+        Frequency::EveryDays(count) => format!("FREQ=DAILY;INTERVAL={}", count),
+    };
+    if let Some(until) = recurrence.until {
+        rule.push_str(&format!(";UNTIL={}", until.format("%Y%m%d")));
+    }
+    rule
+}
+
+/// Explains where calendar applications differ from `days` for the rule.
+fn export_notes(event: &Event, recurrence: &Recurrence) -> Vec<String> {
+    let mut notes: Vec<String> = Vec::new();
+    match recurrence.frequency {
+        Frequency::Yearly if event.date.month() == 2 && event.date.day() == 29 => {
+            notes.push(format!("{}: calendars skip common years instead of using February 28", event));
+        },
+        Frequency::Monthly(day) if day > 28 => {
+            notes.push(format!("{}: calendars skip months shorter than {} days instead of using the last day", event, day));
+        },
+        Frequency::Weekly(weekday) if event.date.weekday() != weekday => {
+            notes.push(format!("{}: calendars also show the first date, which is a {} and not a {}",
+                event, event.date.weekday(), weekday));
+        },
+        _ => {},
+    }
+    notes
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_weekday_code(code: &str) -> Option<Weekday> {
+    [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun]
+        .into_iter()
+        .find(|weekday| weekday_code(*weekday) == code)
+}
+
+/// Escapes text for a property value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {},
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits a property value at unescaped commas and removes the escapes.
+fn unescape_list(value: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => item.push('\n'),
+                Some(c) => item.push(c),
+                None => {},
+            },
+            ',' => items.push(std::mem::take(&mut item)),
+            c => item.push(c),
+        }
+    }
+    items.push(item);
+    items
+}
+
+/// Folds a long line into several, each continuation starting with a space.
+/// Lines are only broken between characters, never inside one.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// Joins folded lines back together, keeping track of the line
+/// number in the file where each one started.
+fn unfold(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            if let Some((_, last)) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        if !line.is_empty() {
+            lines.push((index + 1, line.to_string()));
+        }
+    }
+    lines
+}
+
+/// A VEVENT as read from the calendar, before it is turned into an event.
+#[derive(Debug, Default)]
+struct Component {
+    line: usize,
+    start: Option<String>,
+    summary: Option<String>,
+    categories: Vec<String>,
+    rule: Option<String>,
+    ignored: Vec<String>,
+}
+
+/// Reads the all-day events from an iCalendar calendar. Events without
+/// a category get `default_category`.
+pub fn import(text: &str, default_category: &str) -> Import {
+    let mut events: Vec<Event> = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+    let mut current: Option<Component> = None;
+
+    for (line_number, line) in unfold(text).into_iter() {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, value),
+            None => {
+                notes.push(format!("line {}: not a property, ignored", line_number));
+                continue;
+            }
+        };
+        // Parameters like VALUE=DATE or TZID=... come after the name.
+        let name = name.split(';').next().unwrap_or(name).to_uppercase();
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(Component { line: line_number, ..Component::default() });
+            },
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(component) = current.take() {
+                    events.extend(to_event(&component, default_category, &mut notes));
+                }
+            },
+            ("DTSTART", Some(component)) => component.start = Some(value.to_string()),
+            ("SUMMARY", Some(component)) => component.summary = Some(unescape_list(value).join(",")),
+            ("CATEGORIES", Some(component)) => {
+                component.categories.extend(unescape_list(value).into_iter().filter(|c| !c.is_empty()));
+            },
+            ("RRULE", Some(component)) => component.rule = Some(value.to_string()),
+            ("EXDATE" | "RDATE" | "EXRULE", Some(component)) => component.ignored.push(name),
+            _ => {},
+        }
+    }
+
+    if let Some(component) = current {
+        notes.push(format!("line {}: event is missing END:VEVENT, skipped", component.line));
+    }
+
+    Import { events, notes }
+}
+
+fn to_event(component: &Component, default_category: &str, notes: &mut Vec<String>) -> Option<Event> {
+    let line = component.line;
+
+    let start = match &component.start {
+        Some(start) => start,
+        None => {
+            notes.push(format!("line {}: event has no DTSTART, skipped", line));
+            return None;
+        }
+    };
+    let date = match start.get(..8).and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok()) {
+        Some(date) => date,
+        None => {
+            notes.push(format!("line {}: invalid DTSTART '{}', skipped", line, start));
+            return None;
+        }
+    };
+    if start.len() > 8 {
+        notes.push(format!("line {}: the time of day in DTSTART '{}' was dropped", line, start));
+    }
+
+    let description = component.summary.clone().unwrap_or_default();
+    if description.is_empty() {
+        notes.push(format!("line {}: event has no SUMMARY", line));
+    }
+
+    let category = match component.categories.first() {
+        Some(category) => category.clone(),
+        None => default_category.to_string(),
+    };
+    if component.categories.len() > 1 {
+        notes.push(format!("line {}: only the first of the categories {} was kept",
+            line, component.categories.join(", ")));
+    }
+
+    for name in component.ignored.iter() {
+        notes.push(format!("line {}: {} is not supported, ignored", line, name));
+    }
+
+    let recurrence = match &component.rule {
+        Some(rule) => match from_rrule(rule, date) {
+            Ok(recurrence) => Some(recurrence),
+            Err(reason) => {
+                notes.push(format!("line {}: RRULE '{}' could not be mapped ({}), imported as a one-time event",
+                    line, rule, reason));
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut event = Event { date, category, description, recurrence };
+    // Birthdays repeat yearly anyway, so there is no need to say so in the file.
+    if event.kind() == EventKind::Birthday && event.recurrence == Some(YEARLY) {
+        event.recurrence = None;
+    }
+    Some(event)
+}
+
+const YEARLY: Recurrence = Recurrence { frequency: Frequency::Yearly, until: None };
+
+/// Maps the subset of RRULE that `days` can represent.
+fn from_rrule(rule: &str, start: NaiveDate) -> Result<Recurrence, String> {
+    let mut frequency: Option<&str> = None;
+    let mut interval: u32 = 1;
+    let mut by_day: Option<Weekday> = None;
+    let mut by_month_day: Option<u32> = None;
+    let mut until: Option<NaiveDate> = None;
+    let mut count: Option<u32> = None;
+
+    for part in rule.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=').ok_or_else(|| format!("malformed part '{}'", part))?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => frequency = Some(value),
+            "INTERVAL" => {
+                interval = value.parse::<u32>().ok().filter(|n| *n > 0)
+                    .ok_or_else(|| format!("invalid INTERVAL '{}'", value))?;
+            },
+            "BYDAY" => {
+                by_day = Some(parse_weekday_code(&value.to_uppercase())
+                    .ok_or_else(|| format!("BYDAY '{}' is not a single weekday", value))?);
+            },
+            "BYMONTHDAY" => {
+                by_month_day = Some(value.parse::<u32>().ok().filter(|day| (1..=31).contains(day))
+                    .ok_or_else(|| format!("BYMONTHDAY '{}' is not a single day", value))?);
+            },
+            "UNTIL" => {
+                until = Some(value.get(..8).and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                    .ok_or_else(|| format!("invalid UNTIL '{}'", value))?);
+            },
+            "COUNT" => {
+                count = Some(value.parse::<u32>().ok().filter(|n| *n > 0)
+                    .ok_or_else(|| format!("invalid COUNT '{}'", value))?);
+            },
+            // The start of the week only matters for rules with several weekdays.
+            "WKST" => {},
+            other => return Err(format!("{} is not supported", other)),
+        }
+    }
+
+    let frequency = match (frequency.map(str::to_uppercase).as_deref(), interval, by_day, by_month_day) {
+        (Some("YEARLY"), 1, None, None) => Frequency::Yearly,
+        (Some("MONTHLY"), 1, None, day) => Frequency::Monthly(day.unwrap_or(start.day())),
+        (Some("WEEKLY"), 1, weekday, None) => Frequency::Weekly(weekday.unwrap_or(start.weekday())),
+        (Some("WEEKLY"), n, weekday, None) if weekday.is_none_or(|weekday| weekday == start.weekday()) => {
+            Frequency::EveryDays(every_days(n, 7)?)
+        },
+        (Some("DAILY"), n, None, None) => Frequency::EveryDays(every_days(n, 1)?),
+        (Some(frequency), 1, _, _) => return Err(format!("FREQ={} with these options is not supported", frequency)),
+        (Some(frequency), n, _, _) => return Err(format!("FREQ={} with INTERVAL={} is not supported", frequency, n)),
+        (None, _, _, _) => return Err("FREQ is missing".to_string()),
+    };
+
+    // A number of occurrences becomes the date of the last one.
+    let until = match count {
+        Some(count) => {
+            let last = last_occurrence(frequency, start, count)
+                .ok_or_else(|| format!("COUNT={} goes past the last supported date", count))?;
+            Some(until.map_or(last, |until| until.min(last)))
+        },
+        None => until,
+    };
+    Ok(Recurrence { frequency, until })
+}
+
+/// Returns the interval of a DAILY or WEEKLY rule in days, if `days` can repeat that often.
+fn every_days(interval: u32, days_per_interval: u32) -> Result<u32, String> {
+    interval.checked_mul(days_per_interval)
+        .filter(|days| *days <= MAX_EVERY_DAYS)
+        .ok_or_else(|| format!("INTERVAL={} is longer than {} days", interval, MAX_EVERY_DAYS))
+}
+
+/// Returns the date of the `count`th occurrence of an event that started on `start`.
+fn last_occurrence(frequency: Frequency, start: NaiveDate, count: u32) -> Option<NaiveDate> {
+    let recurrence = Recurrence { frequency, until: None };
+    let first = recurrence.next_occurrence(start, start)?;
+    let rest = count - 1;
+    match frequency {
+        Frequency::Yearly => {
+            let year = first.year().checked_add(i32::try_from(rest).ok()?)?;
+            recurrence.next_occurrence(start, NaiveDate::from_ymd_opt(year, 1, 1)?)
+        },
+        Frequency::Monthly(_) => {
+            let month = first.with_day(1)?.checked_add_months(Months::new(rest))?;
+            recurrence.next_occurrence(start, month)
+        },
+        Frequency::Weekly(_) => add_days(first, (rest as i64).checked_mul(7)?),
+        Frequency::EveryDays(days) => add_days(first, (rest as i64).checked_mul(days as i64)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn maps_simple_rules() {
+        let start = date(2024, 1, 15);
+        assert_eq!(from_rrule("FREQ=YEARLY", start), Ok(YEARLY));
+        assert_eq!(from_rrule("FREQ=MONTHLY", start).map(|r| r.frequency), Ok(Frequency::Monthly(15)));
+        assert_eq!(from_rrule("FREQ=WEEKLY;BYDAY=FR", start).map(|r| r.frequency), Ok(Frequency::Weekly(Weekday::Fri)));
+        assert_eq!(from_rrule("FREQ=WEEKLY;INTERVAL=2", start).map(|r| r.frequency), Ok(Frequency::EveryDays(14)));
+        assert_eq!(from_rrule("FREQ=DAILY;INTERVAL=10", start).map(|r| r.frequency), Ok(Frequency::EveryDays(10)));
+        assert_eq!(from_rrule("FREQ=DAILY;UNTIL=20240301T000000Z", start).map(|r| r.until), Ok(Some(date(2024, 3, 1))));
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        let start = date(2024, 1, 15);
+        assert!(from_rrule("FREQ=YEARLY;INTERVAL=2", start).is_err());
+        assert!(from_rrule("FREQ=WEEKLY;BYDAY=MO,FR", start).is_err());
+        assert!(from_rrule("FREQ=HOURLY", start).is_err());
+        assert!(from_rrule("INTERVAL=2", start).is_err());
+        assert!(from_rrule("FREQ=DAILY;COUNT=0", start).is_err());
+    }
+
+    #[test]
+    fn count_becomes_the_last_date() {
+        let start = date(2024, 1, 31);
+        let until = |rule| from_rrule(rule, start).map(|r| r.until);
+        assert_eq!(until("FREQ=DAILY;COUNT=1"), Ok(Some(start)));
+        assert_eq!(until("FREQ=DAILY;INTERVAL=3;COUNT=3"), Ok(Some(date(2024, 2, 6))));
+        assert_eq!(until("FREQ=WEEKLY;BYDAY=FR;COUNT=2"), Ok(Some(date(2024, 2, 9))));
+        assert_eq!(until("FREQ=MONTHLY;COUNT=2"), Ok(Some(date(2024, 2, 29))));
+        assert_eq!(until("FREQ=YEARLY;COUNT=3"), Ok(Some(date(2026, 1, 31))));
+        assert_eq!(until("FREQ=DAILY;COUNT=5;UNTIL=20240202"), Ok(Some(date(2024, 2, 2))));
+    }
+
+    #[test]
+    fn huge_intervals_and_counts_are_errors() {
+        let start = date(2024, 1, 1);
+        assert!(from_rrule("FREQ=WEEKLY;INTERVAL=1000000000", start).is_err());
+        assert!(from_rrule("FREQ=DAILY;INTERVAL=4294967295", start).is_err());
+        assert!(from_rrule("FREQ=DAILY;INTERVAL=36525;COUNT=4294967295", start).is_err());
+        assert!(from_rrule("FREQ=YEARLY;COUNT=4294967295", start).is_err());
+        assert!(from_rrule("FREQ=MONTHLY;COUNT=4294967295", start).is_err());
+        assert!(from_rrule("FREQ=DAILY;COUNT=4294967295", start).is_err());
+        assert_eq!(from_rrule("FREQ=DAILY;COUNT=1000000", start).map(|r| r.until), Ok(Some(date(4761, 11, 27))));
+    }
+
+    #[test]
+    fn unmappable_rules_become_notes() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20240101\r\n\
+            SUMMARY:Sprint\r\nRRULE:FREQ=WEEKLY;INTERVAL=1000000000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let import = import(text, "misc");
+        assert_eq!(import.events.len(), 1);
+        assert_eq!(import.events[0].recurrence, None);
+        assert_eq!(import.notes.len(), 1);
+    }
+}
@@ -0,0 +1,27 @@
+//! Days since or until events.
+//!
+//! This library has the event model, storage and day calculations
+//! used by the `days` command-line utility, for use in other tools.
+
+pub mod backup;
+pub mod birthday;
+pub mod calc;
+pub mod check;
+pub mod config;
+pub mod error;
+pub mod event;
+pub mod filter;
+pub mod ical;
+pub mod locale;
+pub mod lock;
+pub mod milestone;
+pub mod natural;
+pub mod output;
+pub mod recurrence;
+pub mod span;
+pub mod storage;
+pub mod store;
+pub mod workdays;
+
+pub use error::DaysError;
+pub use event::{Event, EventItem, EventKind};
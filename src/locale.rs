@@ -0,0 +1,288 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveDate, Datelike};
+
+mod en;
+mod fi;
+
+/// The languages the output can be in.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Locale {
+    #[default]
+    English,
+    Finnish,
+}
+
+/// The plural categories a language distinguishes between.
+/// English and Finnish only have one and other.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Plural {
+    One,
+    Other,
+}
+
+/// Units of time in counts like "3 days".
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Unit {
+    Day,
+    BusinessDay,
+    Week,
+    Month,
+    Year,
+}
+
+/// The English plural name of the unit, as in the JSON output.
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Unit::Day => write!(f, "days"),
+            Unit::BusinessDay => write!(f, "business days"),
+            Unit::Week => write!(f, "weeks"),
+            Unit::Month => write!(f, "months"),
+            Unit::Year => write!(f, "years"),
+        }
+    }
+}
+
+/// The messages in the catalogs. Counts are given as numbers and
+/// dates as already formatted text, so that each language can put
+/// them in the right form and order.
+#[derive(Debug, Clone, Copy)]
+pub enum Message<'a> {
+    /// The event is today.
+    Today,
+    /// The event is this many units in the future.
+    In(i64, Unit),
+    /// The event was this many units ago.
+    Ago(i64, Unit),
+    /// A recurring event that first happened `since` days ago.
+    NextOn { date: &'a str, since: i64 },
+    BirthdayToday { years: i32 },
+    TurnsIn { years: i32, days: i64, unit: Unit },
+    /// Age as full years and the days since the last birthday.
+    Age { years: i32, days: i64 },
+    WillBeBorn { name: &'a str, days: i64 },
+    HappyBirthday,
+    YouAre { total_days: i64, age: &'a str },
+    HappyBirthdayTo { name: &'a str, years: i32, total_days: i64 },
+    IsOld { name: &'a str, total_days: i64, age: &'a str },
+    Turning { years: i32, days: i64 },
+    /// Milestones reached today, after an event in the listing.
+    ThatIs(&'a str),
+    /// Milestones reached by the user's own age.
+    RoundNumber(&'a str),
+    /// An upcoming milestone since or until an event.
+    MilestoneOn { date: &'a str, when: &'a str, milestones: &'a str, since: bool, event: &'a str },
+    /// The heading of `days between`. The dates include their weekdays.
+    Between { from: &'a str, to: &'a str, back: bool },
+    SpanFrom { date: &'a str, span: &'a str, result: &'a str },
+    DateIs { input: &'a str, date: &'a str },
+    ConfirmAdd,
+    NothingAdded,
+    Added { id: &'a str, event: &'a str },
+    Removed { id: &'a str, event: &'a str },
+    Changed { id: &'a str, new_id: &'a str, event: &'a str },
+    /// A note that is not an error, like about an unknown category.
+    Note(&'a str),
+    /// The category of an added event is not one of the configured ones.
+    UnknownCategory { category: &'a str, categories: &'a str },
+    /// `days check` found no events file at the path.
+    FileNotFound(&'a str),
+    /// `days check` found no problems in the file.
+    FileOk(&'a str),
+    NoBackups(&'a str),
+    Restored { path: &'a str, backup: &'a str },
+    /// Where the version of the file replaced by a restore was saved.
+    ReplacedVersionIn(&'a str),
+    /// An event in an imported calendar that is already in the events file.
+    AlreadyThere { id: &'a str, event: &'a str },
+    Imported { id: &'a str, event: &'a str },
+    ImportedCount { count: usize, path: &'a str },
+    /// The file was upgraded by `days migrate` from the given layout.
+    Upgraded { path: &'a str, layout: &'a str },
+    UpToDate(&'a str),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseLocaleError(String);
+
+impl fmt::Display for ParseLocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Unknown locale '{}' (expected en or fi)", self.0)
+    }
+}
+
+impl std::error::Error for ParseLocaleError { }
+
+impl FromStr for Locale {
+    type Err = ParseLocaleError;
+
+    /// Parses a language code like `fi`, or a POSIX locale name
+    /// like `fi_FI.UTF-8`, of which only the language matters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.trim()
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Ok(Locale::English),
+            "fi" => Ok(Locale::Finnish),
+            _ => Err(ParseLocaleError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Locale::English => write!(f, "en"),
+            Locale::Finnish => write!(f, "fi"),
+        }
+    }
+}
+
+impl Locale {
+    /// Finds the locale from the first of `LC_ALL`, `LC_MESSAGES` and
+    /// `LANG` that is set. Returns `None` if it is not set or is in a
+    /// language there is no catalog for.
+    pub fn from_env() -> Option<Locale> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse::<Locale>().ok())
+    }
+
+    /// The date format used when none is configured.
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            Locale::English => en::DATE_FORMAT,
+            Locale::Finnish => fi::DATE_FORMAT,
+        }
+    }
+
+    pub fn plural(&self, n: i64) -> Plural {
+        match self {
+            Locale::English => en::plural(n),
+            Locale::Finnish => fi::plural(n),
+        }
+    }
+
+    /// Returns a count with its unit, like "1 day" or "3 days".
+    pub fn count(&self, n: i64, unit: Unit) -> String {
+        match self {
+            Locale::English => en::count(n, unit),
+            Locale::Finnish => fi::count(n, unit),
+        }
+    }
+
+    /// Joins the items like "a, b and c".
+    pub fn and(&self, items: &[String]) -> String {
+        let word = match self {
+            Locale::English => "and",
+            Locale::Finnish => "ja",
+        };
+        match items.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} {} {}", rest.join(", "), word, last),
+            None => String::new(),
+        }
+    }
+
+    /// Joins the alternatives like "a or b".
+    pub fn or(&self, items: &[String]) -> String {
+        let word = match self {
+            Locale::English => " or ",
+            Locale::Finnish => " tai ",
+        };
+        items.join(word)
+    }
+
+    /// Returns true if the answer to a yes or no question means yes.
+    /// An empty answer takes the default, which is yes.
+    pub fn is_yes(&self, answer: &str) -> bool {
+        let answer = answer.trim().to_lowercase();
+        match self {
+            Locale::English => matches!(answer.as_str(), "" | "y" | "yes"),
+            Locale::Finnish => matches!(answer.as_str(), "" | "k" | "kyllä" | "y" | "yes"),
+        }
+    }
+
+    pub fn text(&self, message: Message) -> String {
+        match self {
+            Locale::English => en::text(message),
+            Locale::Finnish => fi::text(message),
+        }
+    }
+
+    /// Formats the date like `NaiveDate::format`, but with the names
+    /// of the months (`%B`, `%b`) and weekdays (`%A`, `%a`) in this
+    /// language. The full month name is in the form used in dates.
+    pub fn format_date(&self, date: NaiveDate, format: &str) -> String {
+        let (months, short_months, weekdays, short_weekdays) = match self {
+            Locale::English => (en::MONTHS, en::SHORT_MONTHS, en::WEEKDAYS, en::SHORT_WEEKDAYS),
+            Locale::Finnish => (fi::MONTHS, fi::SHORT_MONTHS, fi::WEEKDAYS, fi::SHORT_WEEKDAYS),
+        };
+        let month = date.month0() as usize;
+        let weekday = date.weekday().num_days_from_monday() as usize;
+
+        let mut localized = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                localized.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('B') => localized.push_str(months[month]),
+                Some('b') | Some('h') => localized.push_str(short_months[month]),
+                Some('A') => localized.push_str(weekdays[weekday]),
+                Some('a') => localized.push_str(short_weekdays[weekday]),
+                Some(other) => {
+                    localized.push('%');
+                    localized.push(other);
+                },
+                None => localized.push('%'),
+            }
+        }
+        date.format(&localized).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_take_the_plural_form() {
+        assert_eq!(Locale::English.count(1, Unit::Day), "1 day");
+        assert_eq!(Locale::English.count(-1, Unit::Week), "-1 week");
+        assert_eq!(Locale::English.count(3, Unit::BusinessDay), "3 business days");
+        assert_eq!(Locale::Finnish.count(1, Unit::Year), "1 vuosi");
+        assert_eq!(Locale::Finnish.count(0, Unit::Month), "0 kuukautta");
+    }
+
+    #[test]
+    fn finnish_turns_one_year() {
+        let turns = |years| Locale::Finnish.text(Message::TurnsIn { years, days: 3, unit: Unit::Day });
+        assert_eq!(turns(1), "täyttää 1 vuoden 3 päivän päästä");
+        assert_eq!(turns(2), "täyttää 2 vuotta 3 päivän päästä");
+        assert_eq!(Locale::Finnish.text(Message::Turning { years: 1, days: 1 }), " 1 vuosi täynnä 1 päivän päästä.");
+    }
+
+    #[test]
+    fn imported_count_takes_the_plural_form() {
+        let imported = |locale: Locale, count| locale.text(Message::ImportedCount { count, path: "events.csv" });
+        assert_eq!(imported(Locale::English, 1), "Imported 1 event into events.csv");
+        assert_eq!(imported(Locale::English, 0), "Imported 0 events into events.csv");
+        assert_eq!(imported(Locale::Finnish, 1), "Tuotu 1 tapahtuma tiedostoon events.csv");
+    }
+
+    #[test]
+    fn parses_posix_locale_names() {
+        assert_eq!("fi_FI.UTF-8".parse::<Locale>(), Ok(Locale::Finnish));
+        assert_eq!("C".parse::<Locale>(), Ok(Locale::English));
+        assert!("sv_SE".parse::<Locale>().is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! The English message catalog.
+
+use super::{Message, Plural, Unit};
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+pub const SHORT_MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+pub const WEEKDAYS: [&str; 7] = [
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday",
+];
+
+pub const SHORT_WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+pub fn plural(n: i64) -> Plural {
+    if n.abs() == 1 { Plural::One } else { Plural::Other }
+}
+
+pub fn count(n: i64, unit: Unit) -> String {
+    let (one, other) = match unit {
+        Unit::Day => ("day", "days"),
+        Unit::BusinessDay => ("business day", "business days"),
+        Unit::Week => ("week", "weeks"),
+        Unit::Month => ("month", "months"),
+        Unit::Year => ("year", "years"),
+    };
+    match plural(n) {
+        Plural::One => format!("{} {}", n, one),
+        Plural::Other => format!("{} {}", n, other),
+    }
+}
+
+pub fn text(message: Message) -> String {
+    match message {
+        Message::Today => "today".to_string(),
+        Message::In(n, unit) => format!("in {}", count(n, unit)),
+        Message::Ago(n, unit) => format!("{} ago", count(n, unit)),
+        Message::NextOn { date, since } => {
+            format!(" (next on {}, first {} ago)", date, count(since, Unit::Day))
+        },
+        Message::BirthdayToday { years } => format!("Happy birthday! Turns {} today", years),
+        Message::TurnsIn { years, days, unit } => format!("turns {} in {}", years, count(days, unit)),
+        Message::Age { years, days } => {
+            format!("{} and {}", count(years as i64, Unit::Year), count(days, Unit::Day))
+        },
+        Message::WillBeBorn { name, days } => format!("{} will be born in {}.", name, count(days, Unit::Day)),
+        Message::HappyBirthday => "Happy birthday! ".to_string(),
+        Message::YouAre { total_days, age } => {
+            format!("You are {} old ({}).", count(total_days, Unit::Day), age)
+        },
+        Message::HappyBirthdayTo { name, years, total_days } => {
+            format!("Happy birthday, {}! {} turns {} today ({} old).", name, name, years, count(total_days, Unit::Day))
+        },
+        Message::IsOld { name, total_days, age } => {
+            format!("{} is {} old ({}).", name, count(total_days, Unit::Day), age)
+        },
+        Message::Turning { years, days } => format!(" Turning {} in {}.", years, count(days, Unit::Day)),
+        Message::ThatIs(milestones) => format!(" - that's {}!", milestones),
+        Message::RoundNumber(milestones) => format!(" That's a nice round number: {}!", milestones),
+        Message::MilestoneOn { date, when, milestones, since, event } => {
+            let direction = if since { "since" } else { "until" };
+            format!("{} ({}): {} {} {}", date, when, milestones, direction, event)
+        },
+        Message::Between { from, to, back } => {
+            format!("From {} to {}{}:", from, to, if back { ", going back" } else { "" })
+        },
+        Message::SpanFrom { date, span, result } => format!("{} {} is {}", date, span, result),
+        Message::DateIs { input, date } => format!("'{}' is {}", input, date),
+        Message::ConfirmAdd => "Add the event? [Y/n] ".to_string(),
+        Message::NothingAdded => "Nothing added".to_string(),
+        Message::Added { id, event } => format!("Added {}  {}", id, event),
+        Message::Removed { id, event } => format!("Removed {}  {}", id, event),
+        Message::Changed { id, new_id, event } => format!("Changed {} to {}  {}", id, new_id, event),
+        Message::Note(note) => format!("Note: {}", note),
+        Message::UnknownCategory { category, categories } => {
+            format!("Note: '{}' is not one of the configured categories ({})", category, categories)
+        },
+        Message::FileNotFound(path) => format!("{}: not found", path),
+        Message::FileOk(path) => format!("{}: OK", path),
+        Message::NoBackups(path) => format!("No backups of {}", path),
+        Message::Restored { path, backup } => format!("Restored {} from {}", path, backup),
+        Message::ReplacedVersionIn(path) => format!("The replaced version is in {}", path),
+        Message::AlreadyThere { id, event } => format!("Already there {}  {}", id, event),
+        Message::Imported { id, event } => format!("Imported {}  {}", id, event),
+        Message::ImportedCount { count, path } => match plural(count as i64) {
+            Plural::One => format!("Imported {} event into {}", count, path),
+            Plural::Other => format!("Imported {} events into {}", count, path),
+        },
+        Message::Upgraded { path, layout } => format!("{}: upgraded from {}", path, layout),
+        Message::UpToDate(path) => format!("{}: up to date", path),
+    }
+}
@@ -0,0 +1,131 @@
+//! The Finnish message catalog.
+//!
+//! After numbers other than one, Finnish nouns are in the partitive
+//! ("3 päivää"), except in phrases like "3 päivän päästä" (in 3 days),
+//! which take the genitive singular for every number.
+
+use super::{Message, Plural, Unit};
+
+pub const DATE_FORMAT: &str = "%-d.%-m.%Y";
+
+/// Month names in the form used in dates, like "24. joulukuuta".
+pub const MONTHS: [&str; 12] = [
+    "tammikuuta", "helmikuuta", "maaliskuuta", "huhtikuuta", "toukokuuta", "kesäkuuta",
+    "heinäkuuta", "elokuuta", "syyskuuta", "lokakuuta", "marraskuuta", "joulukuuta",
+];
+
+pub const SHORT_MONTHS: [&str; 12] = [
+    "tammi", "helmi", "maalis", "huhti", "touko", "kesä",
+    "heinä", "elo", "syys", "loka", "marras", "joulu",
+];
+
+pub const WEEKDAYS: [&str; 7] = [
+    "maanantai", "tiistai", "keskiviikko", "torstai", "perjantai", "lauantai", "sunnuntai",
+];
+
+pub const SHORT_WEEKDAYS: [&str; 7] = ["ma", "ti", "ke", "to", "pe", "la", "su"];
+
+pub fn plural(n: i64) -> Plural {
+    if n.abs() == 1 { Plural::One } else { Plural::Other }
+}
+
+pub fn count(n: i64, unit: Unit) -> String {
+    let (one, other) = match unit {
+        Unit::Day => ("päivä", "päivää"),
+        Unit::BusinessDay => ("työpäivä", "työpäivää"),
+        Unit::Week => ("viikko", "viikkoa"),
+        Unit::Month => ("kuukausi", "kuukautta"),
+        Unit::Year => ("vuosi", "vuotta"),
+    };
+    match plural(n) {
+        Plural::One => format!("{} {}", n, one),
+        Plural::Other => format!("{} {}", n, other),
+    }
+}
+
+/// Returns the years for "täyttää" (turns), which takes the genitive
+/// for one ("täyttää 1 vuoden") and the partitive for other numbers.
+fn years_turned(n: i32) -> String {
+    match plural(n as i64) {
+        Plural::One => format!("{} vuoden", n),
+        Plural::Other => format!("{} vuotta", n),
+    }
+}
+
+/// Returns the count in the genitive, for "päästä" (in some time).
+fn genitive(n: i64, unit: Unit) -> String {
+    let word = match unit {
+        Unit::Day => "päivän",
+        Unit::BusinessDay => "työpäivän",
+        Unit::Week => "viikon",
+        Unit::Month => "kuukauden",
+        Unit::Year => "vuoden",
+    };
+    format!("{} {}", n, word)
+}
+
+pub fn text(message: Message) -> String {
+    match message {
+        Message::Today => "tänään".to_string(),
+        Message::In(n, unit) => format!("{} päästä", genitive(n, unit)),
+        Message::Ago(n, unit) => format!("{} sitten", count(n, unit)),
+        Message::NextOn { date, since } => {
+            format!(" (seuraavan kerran {}, ensimmäisen kerran {} sitten)", date, count(since, Unit::Day))
+        },
+        Message::BirthdayToday { years } => format!("Hyvää syntymäpäivää! Täyttää tänään {}", years_turned(years)),
+        Message::TurnsIn { years, days, unit } => format!("täyttää {} {} päästä", years_turned(years), genitive(days, unit)),
+        Message::Age { years, days } => {
+            format!("{} ja {}", count(years as i64, Unit::Year), count(days, Unit::Day))
+        },
+        Message::WillBeBorn { name, days } => {
+            format!("{} syntyy {} päästä.", name, genitive(days, Unit::Day))
+        },
+        Message::HappyBirthday => "Hyvää syntymäpäivää! ".to_string(),
+        Message::YouAre { total_days, age } => {
+            format!("Olet {} vanha ({}).", count(total_days, Unit::Day), age)
+        },
+        Message::HappyBirthdayTo { name, years, total_days } => {
+            format!("Hyvää syntymäpäivää, {}! {} täyttää tänään {} ({} vanha).",
+                name, name, years_turned(years), count(total_days, Unit::Day))
+        },
+        Message::IsOld { name, total_days, age } => {
+            format!("{} on {} vanha ({}).", name, count(total_days, Unit::Day), age)
+        },
+        Message::Turning { years, days } => {
+            format!(" {} täynnä {} päästä.", count(years as i64, Unit::Year), genitive(days, Unit::Day))
+        },
+        Message::ThatIs(milestones) => format!(" - se on {}!", milestones),
+        Message::RoundNumber(milestones) => format!(" Sehän on tasaluku: {}!", milestones),
+        Message::MilestoneOn { date, when, milestones, since, event } => {
+            let direction = if since { "tapahtumasta" } else { "tapahtumaan" };
+            format!("{} ({}): {} {} {}", date, when, milestones, direction, event)
+        },
+        Message::Between { from, to, back } => {
+            format!("{} – {}{}:", from, to, if back { " (taaksepäin)" } else { "" })
+        },
+        Message::SpanFrom { date, span, result } => format!("{} {} on {}", date, span, result),
+        Message::DateIs { input, date } => format!("'{}' on {}", input, date),
+        Message::ConfirmAdd => "Lisätäänkö tapahtuma? [K/e] ".to_string(),
+        Message::NothingAdded => "Mitään ei lisätty".to_string(),
+        Message::Added { id, event } => format!("Lisätty {}  {}", id, event),
+        Message::Removed { id, event } => format!("Poistettu {}  {}", id, event),
+        Message::Changed { id, new_id, event } => format!("Muutettu {}, uusi tunnus {}  {}", id, new_id, event),
+        Message::Note(note) => format!("Huom: {}", note),
+        Message::UnknownCategory { category, categories } => {
+            format!("Huom: '{}' ei ole mikään asetetuista luokista ({})", category, categories)
+        },
+        Message::FileNotFound(path) => format!("{}: ei löydy", path),
+        Message::FileOk(path) => format!("{}: kunnossa", path),
+        Message::NoBackups(path) => format!("Tiedostosta {} ei ole varmuuskopioita", path),
+        Message::Restored { path, backup } => format!("{} palautettu varmuuskopiosta {}", path, backup),
+        Message::ReplacedVersionIn(path) => format!("Korvattu versio on tiedostossa {}", path),
+        Message::AlreadyThere { id, event } => format!("Jo olemassa {}  {}", id, event),
+        Message::Imported { id, event } => format!("Tuotu {}  {}", id, event),
+        Message::ImportedCount { count, path } => match plural(count as i64) {
+            Plural::One => format!("Tuotu {} tapahtuma tiedostoon {}", count, path),
+            Plural::Other => format!("Tuotu {} tapahtumaa tiedostoon {}", count, path),
+        },
+        Message::Upgraded { path, layout } => format!("{}: päivitetty muodosta {}", path, layout),
+        Message::UpToDate(path) => format!("{}: ajan tasalla", path),
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::DaysError;
+
+/// How long to wait for another `days` to finish with an events file
+/// when not configured, in seconds.
+pub const DEFAULT_LOCK_TIMEOUT: u64 = 10;
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An advisory lock on an events file, held until it is dropped.
+///
+/// The lock is taken on a separate lock file next to the events file,
+/// because the events file itself is replaced on every write.
+#[derive(Debug)]
+pub struct Lock {
+    // Closing the file releases the lock.
+    _file: File,
+}
+
+/// Returns the path of the lock file for an events file,
+/// like `.events.csv.lock` for `events.csv`.
+pub fn get_lock_path(events_path: &Path) -> PathBuf {
+    let file_name = match events_path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => "events".to_string(),
+    };
+    events_path.with_file_name(format!(".{}.lock", file_name))
+}
+
+impl Lock {
+    /// Locks the events file for changes, waiting up to `timeout`
+    /// for another process to release it.
+    pub fn acquire(events_path: &Path, timeout: Duration) -> Result<Self, DaysError> {
+        let path = get_lock_path(events_path);
+        let file = match OpenOptions::new().create(true).truncate(false).write(true).open(&path) {
+            Ok(file) => file,
+            Err(source) => return Err(DaysError::LockError { path, source }),
+        };
+
+        let start = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Lock { _file: file }),
+                Err(TryLockError::WouldBlock) => {
+                    if start.elapsed() >= timeout {
+                        return Err(DaysError::LockTimeout { path: events_path.to_path_buf(), timeout });
+                    }
+                    thread::sleep(RETRY_INTERVAL);
+                },
+                Err(TryLockError::Error(source)) => {
+                    return Err(DaysError::LockError { path, source });
+                },
+            }
+        }
+    }
+
+    /// Locks several events files, always in the same order so that
+    /// two processes locking the same files cannot deadlock.
+    pub fn acquire_all(events_paths: &[PathBuf], timeout: Duration) -> Result<Vec<Self>, DaysError> {
+        let mut paths: Vec<&PathBuf> = events_paths.iter().collect();
+        paths.sort();
+        paths.dedup();
+
+        let mut locks: Vec<Lock> = Vec::new();
+        for path in paths {
+            locks.push(Lock::acquire(path, timeout)?);
+        }
+        Ok(locks)
+    }
+}
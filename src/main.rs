@@ -1,195 +1,1096 @@
 use std::env;
-use std::io;
-use std::error::Error;
-use std::path::{Path, PathBuf};
 use std::fmt;
-use chrono::{NaiveDate, Datelike, DateTime, Local};
-use csv::{Writer, ReaderBuilder};
-
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct Event {
-    date: NaiveDate,
-    category: String,
-    description: String,
-}
-
-impl fmt::Display for Event {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}: {} ({})", self.date.to_string(), self.description, self.category)
-    }
-}
-
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct EventItem {
-    days: i64,
-    event: Event,
-}
-
-#[derive(Debug, Clone)]
-enum DaysError {
-    HomeDirectoryNotFound,
-    WorkingDirectoryNotFound,
-    CreateError,
-    WriteError,
-    ReadError,
-    InvalidDate,
-}
-
-impl fmt::Display for DaysError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        match self {
-            DaysError::HomeDirectoryNotFound => {
-                write!(f, "Home directory not found")
-            },
-            DaysError::WorkingDirectoryNotFound => {
-                write!(f, "Working directory not found")
-            },
-            DaysError::ReadError => {
-                write!(f, "Error reading events")
-            },
-            DaysError::WriteError => {
-                write!(f, "Error writing events")
-            },
-            DaysError::CreateError => {
-                write!(f, "Unable to crate working directory")
-            },
-            DaysError::InvalidDate => {
-                write!(f, "Invalid date")
-            },
-        }
-    }
-}
-
-impl std::error::Error for DaysError { }
-
-fn run(_args: &[String]) -> Result<(), DaysError> {
-    print_birthday();
-
-    let mut events: Vec<Event> = Vec::new();
-
-    if let Some(path) = get_days_path() {
-        // Create the working directory if it does not exist.
-        if !Path::exists(path.as_path()) {
-            match std::fs::create_dir(path.as_path()) {
-                Ok(_) => {},
-                Err(_) => {
-                    return Err(DaysError::CreateError);
-                }
-            }
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::NaiveDate;
+use regex::RegexBuilder;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use days::{Event, EventItem, DaysError};
+use days::backup::{find_backup, list_backups, restore_backup};
+use days::birthday::{self, Birthday};
+use days::calc::{add_days, days_between, months_and_days, Clock, FixedClock, SystemClock};
+use days::check::check_events;
+use days::config::{Config, CONFIG_FILE_NAME};
+use days::filter::{sort_items, Filter, SortOrder};
+use days::ical;
+use days::locale::{Locale, Message, Unit};
+use days::lock::Lock;
+use days::milestone::{self, Rule};
+use days::natural;
+use days::output::{EventRecord, Listing};
+use days::recurrence::Recurrence;
+use days::span::{self, Span};
+use days::store::Loaded;
+use days::storage::{
+    get_days_path, get_events_paths, get_source_names, find_event_id,
+    load_events, load_event_files, load_all_events, save_events, migrate_events, ReadMode,
+};
+use days::workdays::{read_holidays, Calendar};
+
+/// Writes to standard output like `print!`, but when the reader has gone
+/// away, like `head` after the lines it wanted, exits quietly instead of
+/// panicking.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        write_out(format_args!($($arg)*))
+    };
+}
+
+/// Writes a line to standard output like `println!`; see `out!`.
+macro_rules! outln {
+    () => {
+        write_out(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        write_out(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Show days since or until events in the terminal.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// Use this events file instead of the one in the days directory;
+    /// repeat to merge several files (new events go to the first one).
+    /// The extension sets the format: .csv, .json, .toml or .sqlite
+    #[arg(long, global = true, value_name = "PATH")]
+    file: Vec<PathBuf>,
+
+    /// Count days relative to this date instead of the real today
+    #[arg(long, global = true, alias = "date", value_name = "YYYY-MM-DD", env = "DAYS_TODAY")]
+    today: Option<String>,
+
+    /// Comma-separated milestone rules: multiple:<days>, powers-of-ten,
+    /// repdigit, weeks, months, years
+    #[arg(long, global = true, value_name = "RULES", env = "DAYS_MILESTONES", value_delimiter = ',')]
+    milestones: Vec<String>,
+
+    /// Fail on rows that are not valid events instead of skipping them
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Count business days, leaving out weekends and holidays
+    #[arg(long, global = true)]
+    business_days: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List events with the number of days until or since them (the default)
+    List(ListArgs),
+
+    /// Add a new event
+    Add {
+        /// Date of the event: YYYY-MM-DD, or in words like tomorrow,
+        /// "next friday", "in 3 weeks", "dec 24", 24.12.2026 or 12/24
+        date: String,
+        /// Category of the event
+        category: String,
+        /// Description of the event; several words are joined together
+        #[arg(required = true)]
+        description: Vec<String>,
+        /// Repeat the event: yearly, monthly:<day>, weekly:<weekday> or
+        /// every:<days>, optionally followed by ;until=<YYYY-MM-DD>
+        #[arg(long, value_name = "RULE")]
+        repeat: Option<String>,
+        /// Add the event without asking to confirm a date given in words
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// Remove an event, or one of several identical ones
+    Remove {
+        /// Identifier of the event, or an unambiguous prefix of it
+        id: String,
+    },
+
+    /// Change one field of an event
+    Edit {
+        /// Identifier of the event, or an unambiguous prefix of it
+        id: String,
+        /// Field to change
+        field: Field,
+        /// New value of the field; several words are joined together
+        #[arg(required = true)]
+        value: Vec<String>,
+    },
+
+    /// Show ages and upcoming birthdays of everyone in the events file
+    Birthday,
+
+    /// List upcoming milestones, like 10000 days since an event
+    Milestones {
+        /// How many days ahead to look, at most 36525 (a hundred years)
+        #[arg(long, default_value_t = 30, value_name = "DAYS",
+            value_parser = clap::value_parser!(u32).range(..=36525))]
+        within: u32,
+    },
+
+    /// Show the settings in effect
+    Config,
+
+    /// Check the events files for bad rows, duplicates, empty descriptions
+    /// and unknown categories
+    Check,
+
+    /// Restore the events file from a backup; without a backup,
+    /// list the backups there are
+    Restore {
+        /// Number of the backup in the list (1 is the newest), or its file name
+        backup: Option<String>,
+    },
+
+    /// Add the all-day events from an iCalendar (.ics) file
+    Import {
+        /// The calendar file to read
+        path: PathBuf,
+        /// Category for events that have none in the calendar
+        #[arg(long, default_value = "imported")]
+        category: String,
+    },
+
+    /// Count the days, weeks, months and years between two dates
+    Between {
+        /// The first date, in YYYY-MM-DD format
+        from: String,
+        /// The second date, in YYYY-MM-DD format
+        to: String,
+    },
+
+    /// Find the date some span of time before or after a date
+    From {
+        /// The date to start from, in YYYY-MM-DD format
+        date: String,
+        /// How far to go, like +90d, -2w, 3m or 1y
+        #[arg(allow_hyphen_values = true)]
+        span: String,
+    },
+
+    /// Upgrade the events files to the current layout, keeping a backup
+    Migrate,
+
+    /// Write all the events as an iCalendar (.ics) calendar
+    Export {
+        /// Write the calendar to this file instead of standard output
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Default, Args)]
+struct ListArgs {
+    /// Only list events in this category (can be repeated)
+    #[arg(long, value_name = "CATEGORY")]
+    category: Vec<String>,
+
+    /// Leave out events in this category (can be repeated)
+    #[arg(long, value_name = "CATEGORY")]
+    exclude_category: Vec<String>,
+
+    /// Only list events on or after this date
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    since: Option<String>,
+
+    /// Only list events on or before this date
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    until: Option<String>,
+
+    /// Only list events within this span from today, like 30d, 2w, 3m or 1y
+    #[arg(long, value_name = "SPAN")]
+    within: Option<String>,
+
+    /// Only list past events
+    #[arg(long, conflicts_with = "future")]
+    past: bool,
+
+    /// Only list today's and future events
+    #[arg(long)]
+    future: bool,
+
+    /// Only list events with this text in the description, ignoring case
+    #[arg(long, value_name = "TEXT")]
+    search: Option<String>,
+
+    /// Only list events with a description matching this regular expression,
+    /// ignoring case
+    #[arg(long, value_name = "PATTERN")]
+    regex: Option<String>,
+
+    /// How to order the events
+    #[arg(long, value_enum, default_value_t = Sort::Upcoming)]
+    sort: Sort,
+
+    /// List the events in reverse order
+    #[arg(long)]
+    reverse: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// One line of text per event
+    #[default]
+    Text,
+    /// One JSON document with all the events
+    Json,
+    /// One JSON object per line for each event
+    Ndjson,
+}
+
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+enum Sort {
+    /// Soonest upcoming first, then the most recent past events
+    #[default]
+    Upcoming,
+    /// By the listed date (the next occurrence for recurring events)
+    Date,
+    /// By distance from today in either direction, nearest first
+    Distance,
+    /// By category, then by date
+    Category,
+    /// By description, then by date
+    Description,
+}
+
+impl From<Sort> for SortOrder {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Upcoming => SortOrder::Upcoming,
+            Sort::Date => SortOrder::Date,
+            Sort::Distance => SortOrder::Distance,
+            Sort::Category => SortOrder::Category,
+            Sort::Description => SortOrder::Description,
         }
+    }
+}
 
-        let mut events_path = path.clone();
-        events_path.push("events.csv");
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Field {
+    Date,
+    Category,
+    Description,
+    /// A recurrence rule like for `add --repeat`, or "none"
+    Recurrence,
+}
 
-        if events_path.as_path().exists() {
-            // Read in the events
-            if let Err(_) = read_events(&mut events, events_path.as_path()) {
-                eprintln!("Error reading events");
-                return Err(DaysError::ReadError);
-            }
+/// Settings for running a command, gathered from the command line,
+/// the environment and the configuration file.
+struct Context {
+    events_paths: Vec<PathBuf>,
+    today: NaiveDate,
+    config: Config,
+    config_path: Option<PathBuf>,
+    mode: ReadMode,
+    /// Whether to count business days.
+    business_days: bool,
+    /// Language of the output.
+    locale: Locale,
+}
+
+fn run(cli: Cli) -> Result<(), DaysError> {
+    let config_path = get_days_path().map(|path| path.join(CONFIG_FILE_NAME));
+    let mut config = match &config_path {
+        Some(path) => load_config(path)?,
+        None => Config::default(),
+    };
+    if !cli.milestones.is_empty() {
+        config.milestones = parse_milestone_rules(&cli.milestones)?;
+    }
+
+    let events_paths = get_events_paths(cli.file, config.format)?;
+    // The clock is stopped on the date given with --today, if any.
+    let fixed = match cli.today {
+        Some(value) => Some(FixedClock(parse_date(&value)?)),
+        None => None,
+    };
+    let clock: &dyn Clock = match &fixed {
+        Some(clock) => clock,
+        None => &SystemClock,
+    };
+    // Read the clock only once, so that all the commands agree on the date
+    // even if the run goes past midnight.
+    let today = clock.today();
+
+    let mode = if cli.strict { ReadMode::Strict } else { ReadMode::Lenient };
+
+    let business_days = cli.business_days || config.business_days;
+    let locale = config.locale.or_else(Locale::from_env).unwrap_or_default();
 
-            for event in events.iter() {
-                println!("{}", event);
+    let context = Context { events_paths, today, config, config_path, mode, business_days, locale };
+
+    match cli.command.unwrap_or(Command::List(ListArgs::default())) {
+        Command::List(args) => {
+            let (sort, reverse, format) = (args.sort.into(), args.reverse, args.format);
+            let filter = make_filter(&context, args)?;
+            list_events(&context, &filter, sort, reverse, format)
+        },
+        Command::Add { date, category, description, repeat, yes } => {
+            add_event(&context, &date, &category, &description.join(" "), repeat.as_deref(), yes)
+        },
+        Command::Remove { id } => remove_event(&context, &id),
+        Command::Edit { id, field, value } => {
+            edit_event(&context, &id, field, &value.join(" "))
+        },
+        Command::Birthday => list_birthdays(&context),
+        Command::Milestones { within } => list_milestones(&context, within),
+        Command::Config => {
+            show_config(&context);
+            Ok(())
+        },
+        Command::Check => check_files(&context),
+        Command::Restore { backup } => restore(&context, backup.as_deref()),
+        Command::Import { path, category } => import_calendar(&context, &path, &category),
+        Command::Export { output } => export_calendar(&context, output.as_deref()),
+        Command::Migrate => migrate(&context),
+        Command::Between { from, to } => {
+            let (from, to) = (parse_date(&from)?, parse_date(&to)?);
+            show_between(&context, load_calendar(&context)?.as_ref(), from, to);
+            Ok(())
+        },
+        Command::From { date, span } => {
+            let (date, span) = (parse_date(&date)?, parse_span(&span)?);
+            show_from(&context, load_calendar(&context)?.as_ref(), date, span)
+        },
+    }
+}
+
+fn load_config(path: &Path) -> Result<Config, DaysError> {
+    match Config::load(path) {
+        Ok(config) => Ok(config),
+        Err(err) => {
+            Err(DaysError::InvalidConfig(err))
+        }
+    }
+}
+
+/// Builds the business day calendar from the configured weekend, the
+/// holidays file and the events in the holiday category, or returns
+/// `None` if not counting business days.
+fn make_calendar(context: &Context, events: &[Event]) -> Result<Option<Calendar>, DaysError> {
+    if !context.business_days {
+        return Ok(None);
+    }
+    let config = &context.config;
+    let mut calendar = Calendar { weekend: config.weekend.clone(), ..Calendar::default() };
+    if let Some(path) = &config.holidays {
+        calendar.holidays = read_holidays(path)?;
+    }
+    if let Some(category) = &config.holiday_category {
+        calendar.holiday_events = events.iter()
+            .filter(|event| event.category == *category)
+            .cloned()
+            .collect();
+    }
+    Ok(Some(calendar))
+}
+
+/// Builds the business day calendar for commands that do not otherwise
+/// read the events, reading them only if holidays come from events.
+fn load_calendar(context: &Context) -> Result<Option<Calendar>, DaysError> {
+    let events = match (context.business_days, &context.config.holiday_category) {
+        (true, Some(_)) => warn(load_all_events(&context.events_paths, context.mode)?),
+        _ => Vec::new(),
+    };
+    make_calendar(context, &events)
+}
+
+/// Builds the event filter from the listing options. Without `--within`,
+/// the window from the configuration file applies.
+fn make_filter(context: &Context, args: ListArgs) -> Result<Filter, DaysError> {
+    let since = match args.since {
+        Some(value) => Some(parse_date(&value)?),
+        None => None,
+    };
+    let until = match args.until {
+        Some(value) => Some(parse_date(&value)?),
+        None => None,
+    };
+
+    let within = match args.within {
+        Some(value) => Some(parse_span(&value)?),
+        None => context.config.window.map(|days| Span { count: days as i64, unit: span::Unit::Days }),
+    };
+
+    let regex = match args.regex {
+        Some(pattern) => match RegexBuilder::new(&pattern).case_insensitive(true).build() {
+            Ok(regex) => Some(regex),
+            Err(err) => return Err(DaysError::InvalidPattern(err)),
+        },
+        None => None,
+    };
+
+    let mut filter = Filter {
+        categories: args.category,
+        excluded_categories: args.exclude_category,
+        since,
+        until,
+        past: args.past,
+        future: args.future,
+        search: args.search,
+        regex,
+    };
+    if let Some(within) = within {
+        filter.limit_to(within, context.today)?;
+    }
+    Ok(filter)
+}
+
+fn list_events(context: &Context, filter: &Filter, sort: SortOrder, reverse: bool, format: OutputFormat) -> Result<(), DaysError> {
+    let today = context.today;
+    let config = &context.config;
+
+    // The events are read once, both for the listing and for the
+    // holidays in them, so that each warning is shown only once.
+    let files = load_event_files(&context.events_paths, context.mode)?;
+    let files: Vec<Vec<Event>> = files.into_iter().map(|(_, loaded)| warn(loaded)).collect();
+    let calendar = make_calendar(context, &files.concat())?;
+
+    let sources = get_source_names(&context.events_paths);
+    let mut items: Vec<EventItem> = Vec::new();
+    for (events, source) in files.into_iter().zip(sources.iter()) {
+        for event in events {
+            let mut item = EventItem::new(event, today);
+            if let Some(calendar) = &calendar {
+                let date = item.next.unwrap_or(item.event.date);
+                item.business_days = Some(calendar.business_days_between(today, date));
             }
+            // Only show where the events come from if there is more than one file.
+            if context.events_paths.len() > 1 {
+                item.source = Some(source.clone());
+            }
+            items.push(item);
         }
+    }
 
-        Ok(())
+    if format == OutputFormat::Text {
+        let events: Vec<&Event> = items.iter().map(|item| &item.event).collect();
+        print_birthday(context, &events);
+    }
+
+    items.retain(|item| {
+        let date = item.next.unwrap_or(item.event.date);
+        filter.matches(&item.event, date, item.days)
+    });
+    sort_items(&mut items, sort);
+    if reverse {
+        items.reverse();
+    }
+
+    if format != OutputFormat::Text {
+        return print_json(context, &items, format);
+    }
+
+    let color = use_color(config);
+    let locale = context.locale;
+    for item in items.iter() {
+        let mut line = item.describe(config.date_format(locale), locale);
+        let milestones = milestone::find(&config.milestones, item.event.date, today);
+        if !milestones.is_empty() {
+            let text = locale.text(Message::ThatIs(&join_milestones(&milestones, locale)));
+            line.push_str(&paint(&text, YELLOW, color));
+        }
+
+        let style = match item.days {
+            0 => BOLD,
+            days if days < 0 => DIM,
+            _ => "",
+        };
+        outln!("{}", paint(&line, style, color));
+    }
+
+    Ok(())
+}
+
+/// Prints the listing in one of the JSON formats described in the README.
+fn print_json(context: &Context, items: &[EventItem], format: OutputFormat) -> Result<(), DaysError> {
+    let records: Vec<EventRecord> = items.iter()
+        .map(|item| EventRecord::new(item, &milestone::find(&context.config.milestones, item.event.date, context.today)))
+        .collect();
+
+    if format == OutputFormat::Ndjson {
+        for record in records.iter() {
+            outln!("{}", serde_json::to_string(record).map_err(DaysError::OutputError)?);
+        }
+    }
+    else {
+        let listing = Listing::new(context.today, records);
+        outln!("{}", serde_json::to_string_pretty(&listing).map_err(DaysError::OutputError)?);
+    }
+    Ok(())
+}
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Colors are used if the configuration allows them, the output goes
+/// to a terminal, and the `NO_COLOR` environment variable is not set.
+fn use_color(config: &Config) -> bool {
+    config.color && std::io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color && !style.is_empty() {
+        format!("{}{}{}", style, text, RESET)
     }
     else {
-        eprintln!(".days path not found!");
-        return Err(DaysError::WorkingDirectoryNotFound)
-    }
-}
-
-fn read_events(events: &mut Vec<Event>, path: &Path) -> Result<(), Box<dyn Error>> {
-    let mut reader = ReaderBuilder::new().has_headers(true).from_path(path)?;
-    for result in reader.records() {
-        let record = result?;
-        let category = record[1].to_string();
-        let description = record[2].to_string();
-        match NaiveDate::parse_from_str(&record[0], "%Y-%m-%d") {
-            Ok(date) => {
-                events.push(Event { date, category, description });
-            },
-            Err(_) => {
-                eprintln!("Invalid timestamp '{}'", record[0].to_string());
+        text.to_string()
+    }
+}
+
+fn list_milestones(context: &Context, within: u32) -> Result<(), DaysError> {
+    let today = context.today;
+    let rules = &context.config.milestones;
+    let locale = context.locale;
+    let date_format = context.config.date_format(locale);
+    let events = warn(load_all_events(&context.events_paths, context.mode)?);
+
+    let mut upcoming: Vec<(NaiveDate, &Event, Vec<milestone::Milestone>)> = Vec::new();
+    for event in events.iter() {
+        for offset in 0..=within {
+            let date = match add_days(today, offset as i64) {
+                Some(date) => date,
+                None => break,
+            };
+            let milestones = milestone::find(rules, event.date, date);
+            if !milestones.is_empty() {
+                upcoming.push((date, event, milestones));
             }
         }
     }
+    upcoming.sort_by_key(|(date, event, _)| (*date, *event));
+
+    for (date, event, milestones) in upcoming.iter() {
+        let days = date.signed_duration_since(today).num_days();
+        let when = match days {
+            0 => locale.text(Message::Today),
+            days => locale.text(Message::In(days, Unit::Day)),
+        };
+        outln!("{}", locale.text(Message::MilestoneOn {
+            date: &locale.format_date(*date, date_format),
+            when: &when,
+            milestones: &join_milestones(milestones, locale),
+            since: *date > event.date,
+            event: &event.describe(date_format, locale),
+        }));
+    }
+
     Ok(())
 }
 
-fn write_events(events: Vec<Event>, path: &Path) -> Result<(), Box<dyn Error>> {
-    let mut writer = Writer::from_path(path)?;
-    writer.write_record(&["date", "category", "description"])?;
-    for event in events.iter() {
-        writer.write_record(&[event.date.to_string(), event.category.clone(), event.description.clone()])?;
+fn join_milestones(milestones: &[milestone::Milestone], locale: Locale) -> String {
+    locale.or(&milestones.iter().map(|m| m.describe(locale)).collect::<Vec<String>>())
+}
+
+fn parse_milestone_rules(values: &[String]) -> Result<Vec<Rule>, DaysError> {
+    let mut rules: Vec<Rule> = Vec::new();
+    for value in values.iter() {
+        match value.parse::<Rule>() {
+            Ok(rule) => rules.push(rule),
+            Err(err) => return Err(DaysError::InvalidMilestoneRule(err)),
+        }
+    }
+    Ok(rules)
+}
+
+fn add_event(context: &Context, date: &str, category: &str, description: &str, repeat: Option<&str>, yes: bool) -> Result<(), DaysError> {
+    let events_path = &context.events_paths[0];
+    let input = date;
+    let date = match natural::parse_date(input, context.today, context.config.date_order, context.config.week_start) {
+        Ok(date) => date,
+        Err(err) => return Err(DaysError::InvalidDateInput(err)),
+    };
+    let recurrence = match repeat {
+        Some(rule) => Some(parse_recurrence(rule)?),
+        None => None,
+    };
+
+    // Echo back a date given in words, and when there is someone to ask,
+    // make sure it is the one they meant before changing anything.
+    let locale = context.locale;
+    let date_format = context.config.date_format(locale);
+    if parse_date(input).is_err() {
+        let resolved = locale.format_date(date, &format!("{} (%A)", date_format));
+        let echo = locale.text(Message::DateIs { input, date: &resolved });
+        if !yes && io::stdin().is_terminal() && io::stderr().is_terminal() {
+            if !confirm(&format!("{}. {}", echo, locale.text(Message::ConfirmAdd)), locale) {
+                eprintln!("{}", locale.text(Message::NothingAdded));
+                return Ok(());
+            }
+        }
+        else {
+            eprintln!("{}", echo);
+        }
+    }
+
+    let _lock = Lock::acquire(events_path, lock_timeout(context))?;
+    // Changes are always read strictly, because any rows skipped
+    // would be lost when the file is written back.
+    let mut events = warn(load_events(events_path, ReadMode::Strict)?);
+
+    let event = Event {
+        date,
+        category: category.to_string(),
+        description: description.to_string(),
+        recurrence,
+    };
+    let message = locale.text(Message::Added { id: &event.id(), event: &event.describe(date_format, locale) });
+    events.push(event);
+
+    save_events(events, events_path, context.config.backups)?;
+    outln!("{}", message);
+    if !context.config.is_known_category(category) {
+        eprintln!("{}", locale.text(Message::UnknownCategory {
+            category,
+            categories: &context.config.categories.join(", "),
+        }));
     }
-    writer.flush()?;
     Ok(())
 }
 
-// See https://blog.liw.fi/posts/2021/10/12/tilde-expansion-crates/ for notes.
+/// Shows the warnings about entries that could not be loaded,
+/// and returns the events that could.
+fn warn(loaded: Loaded) -> Vec<Event> {
+    for warning in loaded.warnings.iter() {
+        eprintln!("{}", warning);
+    }
+    loaded.events
+}
+
+/// Asks a yes or no question on the terminal. Anything but an empty
+/// answer or yes is taken as no.
+fn confirm(question: &str, locale: Locale) -> bool {
+    eprint!("{}", question);
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    locale.is_yes(&answer)
+}
 
-fn get_days_path() -> Option<PathBuf> {
-    // NOTE: Don't use std::env::home_dir to get the home directory,
-    // it doesn't work like it should! Use the `dirs` crate instead.
-    match dirs::home_dir() {
-        Some(home_dir) => {
-            let mut path = home_dir.clone();
-            // Construct a path for the `~/.days` directory:
-            path.push(".days");
-            Some(path)
+fn remove_event(context: &Context, id: &str) -> Result<(), DaysError> {
+    let _locks = Lock::acquire_all(&context.events_paths, lock_timeout(context))?;
+    let files: Vec<(&Path, Vec<Event>)> = load_event_files(&context.events_paths, ReadMode::Strict)?
+        .into_iter()
+        .map(|(path, loaded)| (path, warn(loaded)))
+        .collect();
+    let id = find_event_id(files.iter().flat_map(|(_, events)| events.iter()), id)?;
+
+    // Identical events have the same ID, so only the first one
+    // is removed, and the others are left for later.
+    for (events_path, mut events) in files.into_iter() {
+        let index = match events.iter().position(|event| event.id() == id) {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let event = events.remove(index);
+        save_events(events, events_path, context.config.backups)?;
+        let locale = context.locale;
+        let event = event.describe(context.config.date_format(locale), locale);
+        outln!("{}", locale.text(Message::Removed { id: &id, event: &event }));
+        break;
+    }
+
+    Ok(())
+}
+
+fn edit_event(context: &Context, id: &str, field: Field, value: &str) -> Result<(), DaysError> {
+    let _locks = Lock::acquire_all(&context.events_paths, lock_timeout(context))?;
+    let files: Vec<(&Path, Vec<Event>)> = load_event_files(&context.events_paths, ReadMode::Strict)?
+        .into_iter()
+        .map(|(path, loaded)| (path, warn(loaded)))
+        .collect();
+    let id = find_event_id(files.iter().flat_map(|(_, events)| events.iter()), id)?;
+
+    // Like with removing, only the first of identical events is changed.
+    for (events_path, mut events) in files.into_iter() {
+        let event = match events.iter_mut().find(|event| event.id() == id) {
+            Some(event) => event,
+            None => continue,
+        };
+
+        let changed = change_event(context, event, &id, field, value)?;
+        save_events(events, events_path, context.config.backups)?;
+        outln!("{}", changed);
+        break;
+    }
+
+    Ok(())
+}
+
+/// Changes the field of the event, and returns the message to show
+/// once the change is saved.
+fn change_event(context: &Context, event: &mut Event, id: &str, field: Field, value: &str) -> Result<String, DaysError> {
+    let locale = context.locale;
+    match field {
+        Field::Date => event.date = parse_date(value)?,
+        Field::Category => event.category = value.to_string(),
+        Field::Description => event.description = value.to_string(),
+        Field::Recurrence => {
+            event.recurrence = match value {
+                "none" => None,
+                rule => Some(parse_recurrence(rule)?),
+            };
         },
+    }
+    let description = event.describe(context.config.date_format(locale), locale);
+    Ok(locale.text(Message::Changed { id, new_id: &event.id(), event: &description }))
+}
+
+/// Checks all the events files and reports every problem found.
+fn check_files(context: &Context) -> Result<(), DaysError> {
+    let locale = context.locale;
+    let mut count = 0;
+    for events_path in context.events_paths.iter() {
+        let path = events_path.display().to_string();
+        if !events_path.exists() {
+            outln!("{}", locale.text(Message::FileNotFound(&path)));
+            continue;
+        }
+
+        let problems = check_events(events_path, &context.config.categories)?;
+        if problems.is_empty() {
+            outln!("{}", locale.text(Message::FileOk(&path)));
+        }
+        for problem in problems.iter() {
+            match problem.line {
+                0 => outln!("{}: {}", events_path.display(), problem.kind),
+                line => outln!("{}:{}: {}", events_path.display(), line, problem.kind),
+            }
+        }
+        count += problems.len();
+    }
+
+    if count > 0 {
+        return Err(DaysError::ProblemsFound(count));
+    }
+    Ok(())
+}
+
+/// Restores the first events file from a backup, or lists its backups.
+fn restore(context: &Context, backup: Option<&str>) -> Result<(), DaysError> {
+    let events_path = &context.events_paths[0];
+    let path = events_path.display().to_string();
+    let locale = context.locale;
+
+    let backup = match backup {
+        Some(name) => find_backup(events_path, name)?,
         None => {
-            eprintln!("No home directory for user");
-            None
+            let backups = list_backups(events_path)?;
+            if backups.is_empty() {
+                outln!("{}", locale.text(Message::NoBackups(&path)));
+            }
+            for (index, backup) in backups.iter().enumerate() {
+                outln!("{:>3}  {}  {}", index + 1, backup.created.format("%Y-%m-%d %H:%M:%S"), backup.path.display());
+            }
+            return Ok(());
         }
+    };
+
+    let _lock = Lock::acquire(events_path, lock_timeout(context))?;
+    let previous = restore_backup(&backup, events_path, context.config.backups)?;
+    outln!("{}", locale.text(Message::Restored { path: &path, backup: &backup.path.display().to_string() }));
+    if let Some(previous) = previous {
+        outln!("{}", locale.text(Message::ReplacedVersionIn(&previous.display().to_string())));
     }
+    Ok(())
+}
+
+/// Adds the events from a calendar to the first events file,
+/// leaving out events that are already there.
+fn import_calendar(context: &Context, path: &Path, category: &str) -> Result<(), DaysError> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(source) => return Err(DaysError::ReadError { path: path.to_path_buf(), source }),
+    };
+    let import = ical::import(&text, category);
+    for note in import.notes.iter() {
+        eprintln!("{}: {}", path.display(), note);
+    }
+
+    let events_path = &context.events_paths[0];
+    let _lock = Lock::acquire(events_path, lock_timeout(context))?;
+    let mut events = warn(load_events(events_path, ReadMode::Strict)?);
+
+    let locale = context.locale;
+    let date_format = context.config.date_format(locale);
+    let mut messages: Vec<String> = Vec::new();
+    let mut added = 0;
+    for event in import.events.into_iter() {
+        let (id, description) = (event.id(), event.describe(date_format, locale));
+        if events.iter().any(|existing| existing.id() == id) {
+            messages.push(locale.text(Message::AlreadyThere { id: &id, event: &description }));
+            continue;
+        }
+        messages.push(locale.text(Message::Imported { id: &id, event: &description }));
+        events.push(event);
+        added += 1;
+    }
+
+    if added > 0 {
+        save_events(events, events_path, context.config.backups)?;
+    }
+    for message in messages.iter() {
+        outln!("{}", message);
+    }
+    outln!("{}", locale.text(Message::ImportedCount { count: added, path: &events_path.display().to_string() }));
+    Ok(())
 }
 
-fn print_birthday() {
-    if let Ok(value) = env::var("BIRTHDATE") {
-        match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
-            Ok(birthdate) => {
-                let now: DateTime<Local> = Local::now();
-                match NaiveDate::from_ymd_opt(now.year(), now.month(), now.day()) {
-                    Some(now_date) => {
-                        if birthdate.month() == now_date.month() && birthdate.day() == now_date.day() {
-                            print!("Happy birthday! ");
-                        }
-                        let diff = now_date.signed_duration_since(birthdate);
-                        let day_count = diff.num_days();
-                        print!("You are {} days old.", day_count);
-                        if day_count % 1000 == 0 {
-                            print!(" That's a nice round number!");
-                        }
-                        println!();
-                    },
-                    None => {
-                        eprintln!("Unable to get today's date");
-                    }
-                }
-            },
-            Err(_) => {
-                eprintln!("Error in the value of the BIRTHDATE environment variable: \
-                    '{}' is not a valid date.", value);
+/// Exports the events from all the events files. Notes about events that
+/// calendar applications show differently go to standard error.
+fn export_calendar(context: &Context, output: Option<&Path>) -> Result<(), DaysError> {
+    let events = warn(load_all_events(&context.events_paths, context.mode)?);
+    let export = ical::export(&events, chrono::Utc::now().naive_utc());
+    for note in export.notes.iter() {
+        eprintln!("{}", context.locale.text(Message::Note(note)));
+    }
+
+    match output {
+        Some(path) => {
+            if let Err(source) = std::fs::write(path, &export.text) {
+                return Err(DaysError::WriteError { path: path.to_path_buf(), source });
             }
-        };
+        },
+        None => out!("{}", export.text),
+    }
+    Ok(())
+}
+
+/// Shows the distance between two dates in days, weeks, months and days,
+/// and years, months and days.
+fn show_between(context: &Context, calendar: Option<&Calendar>, from: NaiveDate, to: NaiveDate) {
+    let locale = context.locale;
+    let date_format = format!("{} (%A)", context.config.date_format(locale));
+    let days = days_between(from, to);
+    outln!("{}", locale.text(Message::Between {
+        from: &locale.format_date(from, &date_format),
+        to: &locale.format_date(to, &date_format),
+        back: days < 0,
+    }));
+
+    let days = days.abs();
+    let (months, month_days) = months_and_days(from, to);
+    let months = months as i64;
+    outln!("  {}", locale.count(days, Unit::Day));
+    if let Some(calendar) = calendar {
+        let business_days = calendar.business_days_between(from, to).abs();
+        outln!("  {}", locale.count(business_days, Unit::BusinessDay));
+    }
+    outln!("  {}", locale.and(&[locale.count(days / 7, Unit::Week), locale.count(days % 7, Unit::Day)]));
+    outln!("  {}", locale.and(&[locale.count(months, Unit::Month), locale.count(month_days, Unit::Day)]));
+    outln!("  {}", locale.and(&[locale.count(months / 12, Unit::Year),
+        locale.count(months % 12, Unit::Month), locale.count(month_days, Unit::Day)]));
+}
+
+/// Shows the date a span of time from a date, and the distance to it.
+/// When counting business days, a span in days is in business days.
+fn show_from(context: &Context, calendar: Option<&Calendar>, date: NaiveDate, span: Span) -> Result<(), DaysError> {
+    let (result, description) = match (calendar, span.unit, span.count.checked_abs()) {
+        (_, _, None) => (None, span.to_string()),
+        (Some(calendar), span::Unit::Days, Some(count)) => {
+            let description = context.locale.count(count, Unit::BusinessDay);
+            (calendar.add_business_days(date, span.count), description)
+        },
+        _ => (span.add_to(date), span.to_string()),
+    };
+    let result = match result {
+        Some(result) => result,
+        None => return Err(DaysError::SpanOutOfRange(span.to_string())),
+    };
+    let locale = context.locale;
+    let date_format = context.config.date_format(locale);
+    let sign = if span.count < 0 { "-" } else { "+" };
+    let span = format!("{}{}", sign, description.trim_start_matches('-'));
+    outln!("{}", locale.text(Message::SpanFrom {
+        date: &locale.format_date(date, date_format),
+        span: &span,
+        result: &locale.format_date(result, &format!("{} (%A)", date_format)),
+    }));
+    outln!();
+    show_between(context, calendar, date, result);
+    Ok(())
+}
+
+fn migrate(context: &Context) -> Result<(), DaysError> {
+    for events_path in context.events_paths.iter() {
+        let _lock = Lock::acquire(events_path, lock_timeout(context))?;
+        let path = events_path.display().to_string();
+        match migrate_events(events_path, context.config.backups)? {
+            Some(layout) => outln!("{}", context.locale.text(Message::Upgraded { path: &path, layout: &layout })),
+            None => outln!("{}", context.locale.text(Message::UpToDate(&path))),
+        }
+    }
+    Ok(())
+}
+
+fn lock_timeout(context: &Context) -> Duration {
+    Duration::from_secs(context.config.lock_timeout)
+}
+
+fn show_config(context: &Context) {
+    let config = &context.config;
+
+    match get_days_path() {
+        Some(path) => outln!("days directory: {}", path.display()),
+        None => outln!("days directory: (not found)"),
+    }
+    match &context.config_path {
+        Some(path) if path.exists() => outln!("config file: {}", path.display()),
+        Some(path) => outln!("config file: {} (not found, using defaults)", path.display()),
+        None => outln!("config file: (none)"),
+    }
+    for events_path in context.events_paths.iter() {
+        outln!("events file: {}", events_path.display());
+    }
+    outln!("reference date: {}", context.today);
+    if config.categories.is_empty() {
+        outln!("categories: (any)");
+    }
+    else {
+        outln!("categories: {}", config.categories.join(", "));
+    }
+    outln!("locale: {}", context.locale);
+    outln!("date format: {}", config.date_format(context.locale));
+    outln!("date order: {}", config.date_order);
+    match get_birthdate(config) {
+        Some(date) => outln!("birthdate: {}", date),
+        None => outln!("birthdate: (not set)"),
+    }
+    match config.window {
+        Some(window) => outln!("window: {} days", window),
+        None => outln!("window: (all events)"),
+    }
+    outln!("color: {}", if config.color { "on" } else { "off" });
+    outln!("week start: {}", config.week_start);
+    let rules: Vec<String> = config.milestones.iter().map(|rule| rule.to_string()).collect();
+    outln!("milestones: {}", rules.join(","));
+    outln!("backups: {}", config.backups);
+    outln!("lock timeout: {} seconds", config.lock_timeout);
+    outln!("format: {}", config.format);
+    outln!("business days: {}", if context.business_days { "on" } else { "off" });
+    let weekend: Vec<String> = config.weekend.iter().map(|weekday| weekday.to_string()).collect();
+    if weekend.is_empty() {
+        outln!("weekend: (none)");
+    }
+    else {
+        outln!("weekend: {}", weekend.join(", "));
+    }
+    match &config.holidays {
+        Some(path) => outln!("holidays file: {}", path.display()),
+        None => outln!("holidays file: (none)"),
+    }
+    match &config.holiday_category {
+        Some(category) => outln!("holiday category: {}", category),
+        None => outln!("holiday category: (none)"),
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, DaysError> {
+    match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => Ok(date),
+        Err(_) => Err(DaysError::InvalidDate(value.to_string())),
+    }
+}
+
+fn parse_span(value: &str) -> Result<Span, DaysError> {
+    match value.parse::<Span>() {
+        Ok(span) => Ok(span),
+        Err(err) => Err(DaysError::InvalidSpan(err)),
+    }
+}
+
+fn parse_recurrence(value: &str) -> Result<Recurrence, DaysError> {
+    match value.parse::<Recurrence>() {
+        Ok(recurrence) => Ok(recurrence),
+        Err(err) => Err(DaysError::InvalidRecurrence(err)),
+    }
+}
+
+/// Collects the birthdays in the events, taking the user's own birthdate
+/// from the environment or the configuration if there is no event for it.
+fn get_birthdays(events: &[&Event], config: &Config) -> Vec<Birthday> {
+    birthday::from_events(events, get_birthdate(config))
+}
+
+/// Returns the user's own birthdate, leaving it out with an error message
+/// if `BIRTHDATE` is not a date.
+fn get_birthdate(config: &Config) -> Option<NaiveDate> {
+    match birthday::own_birthdate(config) {
+        Ok(birthdate) => birthdate,
+        Err(err) => {
+            eprintln!("{}", err);
+            None
+        }
+    }
+}
+
+/// Prints the user's own age and birthday greeting, if the birthdate is known.
+fn print_birthday(context: &Context, events: &[&Event]) {
+    let today = context.today;
+    if let Some(own) = get_birthdays(events, &context.config).iter().find(|birthday| birthday.is_own()) {
+        out!("{}", own.report(today, context.locale));
+        // Whole years are already covered by the birthday greeting.
+        let rules: Vec<Rule> = context.config.milestones.iter().copied().filter(|rule| *rule != Rule::Years).collect();
+        let milestones = milestone::find(&rules, own.date, today);
+        if !milestones.is_empty() {
+            out!("{}", context.locale.text(Message::RoundNumber(&join_milestones(&milestones, context.locale))));
+        }
+        outln!();
+    }
+}
+
+fn list_birthdays(context: &Context) -> Result<(), DaysError> {
+    let today = context.today;
+    let events = warn(load_all_events(&context.events_paths, context.mode)?);
+    let events: Vec<&Event> = events.iter().collect();
+
+    let mut birthdays = get_birthdays(&events, &context.config);
+    // The user's own birthday stays first, the rest are in order of the next birthday.
+    birthdays.sort_by_key(|birthday| (!birthday.is_own(), birthday.next(today)));
+
+    for birthday in birthdays.iter() {
+        outln!("{}", birthday.report(today, context.locale));
+    }
+
+    Ok(())
+}
+
+fn write_out(args: fmt::Arguments) {
+    let mut stdout = io::stdout().lock();
+    match stdout.write_fmt(args) {
+        Ok(()) => {},
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => std::process::exit(exitcode::OK),
+        Err(err) => {
+            eprintln!("error: Unable to write the output: {}", err);
+            std::process::exit(exitcode::IOERR);
+        },
     }
 }
 
 fn main() -> Result<(), DaysError> {
     env_logger::init();
 
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
 
-    let result = run(&args[1..]);
+    let result = run(cli);
     std::process::exit(match result {
         Ok(_) => exitcode::OK,
         Err(err) => {
-            eprintln!("error: {:?}", err);
-            exitcode::USAGE
+            log::debug!("{:?}", err);
+            eprintln!("error: {}", err);
+            err.exit_code()
         }
     });
 }
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveDate, Datelike};
+use crate::locale::{Locale, Unit};
+
+/// A rule for deciding which day counts are worth celebrating.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Rule {
+    /// Multiples of the given number of days, like 1000, 2000, ...
+    Multiple(u64),
+    /// 10, 100, 1000, ... days.
+    PowersOfTen,
+    /// Day counts of at least three identical digits, like 111 or 22222.
+    Repdigit,
+    /// Whole weeks.
+    Weeks,
+    /// Whole months, on the same day of the month.
+    Months,
+    /// Whole years, on the same month and day.
+    Years,
+}
+
+/// The rules used when none are configured.
+pub const DEFAULT_RULES: [Rule; 4] = [
+    Rule::Multiple(1000),
+    Rule::PowersOfTen,
+    Rule::Repdigit,
+    Rule::Years,
+];
+
+/// A milestone reached on some date, counting from or to an event.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Milestone {
+    pub rule: Rule,
+    pub count: u64,
+    pub unit: Unit,
+}
+
+impl fmt::Display for Milestone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.describe(Locale::English))
+    }
+}
+
+impl Milestone {
+    /// Describes the milestone like "1000 days", in the language.
+    pub fn describe(&self, locale: Locale) -> String {
+        locale.count(self.count as i64, self.unit)
+    }
+}
+
+impl Rule {
+    /// Returns the milestone this rule finds between the two dates, if any.
+    pub fn check(&self, start: NaiveDate, end: NaiveDate) -> Option<Milestone> {
+        let (first, last) = if start <= end { (start, end) } else { (end, start) };
+        let days = last.signed_duration_since(first).num_days() as u64;
+        if days == 0 {
+            return None;
+        }
+
+        let milestone = |count, unit| Some(Milestone { rule: *self, count, unit });
+
+        match *self {
+            Rule::Multiple(n) if days.is_multiple_of(n) => milestone(days, Unit::Day),
+            Rule::PowersOfTen if is_power_of_ten(days) => milestone(days, Unit::Day),
+            Rule::Repdigit if is_repdigit(days) => milestone(days, Unit::Day),
+            Rule::Weeks if days.is_multiple_of(7) => milestone(days / 7, Unit::Week),
+            Rule::Months if first.day() == last.day() => {
+                let months = (last.year() - first.year()) * 12
+                    + last.month() as i32 - first.month() as i32;
+                milestone(months as u64, Unit::Month)
+            },
+            Rule::Years if first.month() == last.month() && first.day() == last.day() => {
+                milestone((last.year() - first.year()) as u64, Unit::Year)
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Returns the milestones that any of the rules find between the dates.
+/// Several rules can match the same day count, but it is reported only once.
+pub fn find(rules: &[Rule], start: NaiveDate, end: NaiveDate) -> Vec<Milestone> {
+    let mut milestones: Vec<Milestone> = Vec::new();
+    for milestone in rules.iter().filter_map(|rule| rule.check(start, end)) {
+        if !milestones.iter().any(|m| m.count == milestone.count && m.unit == milestone.unit) {
+            milestones.push(milestone);
+        }
+    }
+    milestones
+}
+
+fn is_power_of_ten(mut n: u64) -> bool {
+    if n < 10 {
+        return false;
+    }
+    while n.is_multiple_of(10) {
+        n /= 10;
+    }
+    n == 1
+}
+
+fn is_repdigit(n: u64) -> bool {
+    let digits = n.to_string();
+    digits.len() >= 3 && digits.chars().all(|c| digits.starts_with(c))
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseRuleError(String);
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Invalid milestone rule '{}' \
+            (expected multiple:<days>, powers-of-ten, repdigit, weeks, months or years)", self.0)
+    }
+}
+
+impl std::error::Error for ParseRuleError { }
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rule = s.trim().to_lowercase();
+        match rule.split_once(':') {
+            Some(("multiple", n)) => match n.parse::<u64>() {
+                Ok(n) if n > 0 => Ok(Rule::Multiple(n)),
+                _ => Err(ParseRuleError(s.to_string())),
+            },
+            None if rule == "powers-of-ten" => Ok(Rule::PowersOfTen),
+            None if rule == "repdigit" => Ok(Rule::Repdigit),
+            None if rule == "weeks" => Ok(Rule::Weeks),
+            None if rule == "months" => Ok(Rule::Months),
+            None if rule == "years" => Ok(Rule::Years),
+            _ => Err(ParseRuleError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Rule::Multiple(n) => write!(f, "multiple:{}", n),
+            Rule::PowersOfTen => write!(f, "powers-of-ten"),
+            Rule::Repdigit => write!(f, "repdigit"),
+            Rule::Weeks => write!(f, "weeks"),
+            Rule::Months => write!(f, "months"),
+            Rule::Years => write!(f, "years"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn count(rule: Rule, start: NaiveDate, end: NaiveDate) -> Option<(u64, Unit)> {
+        rule.check(start, end).map(|milestone| (milestone.count, milestone.unit))
+    }
+
+    #[test]
+    fn counts_months_across_years() {
+        let start = date(2023, 11, 15);
+        assert_eq!(count(Rule::Months, start, date(2024, 2, 15)), Some((3, Unit::Month)));
+        assert_eq!(count(Rule::Months, date(2024, 2, 15), start), Some((3, Unit::Month)));
+        assert_eq!(count(Rule::Months, start, date(2025, 11, 15)), Some((24, Unit::Month)));
+        assert_eq!(count(Rule::Months, start, date(2024, 2, 14)), None);
+        assert_eq!(count(Rule::Months, start, start), None);
+    }
+
+    #[test]
+    fn counts_years_from_a_leap_day() {
+        let start = date(2020, 2, 29);
+        assert_eq!(count(Rule::Years, start, date(2024, 2, 29)), Some((4, Unit::Year)));
+        assert_eq!(count(Rule::Years, start, date(2021, 2, 28)), None);
+        assert_eq!(count(Rule::Years, start, date(2021, 3, 1)), None);
+        assert_eq!(count(Rule::Years, date(2023, 3, 1), date(2024, 3, 1)), Some((1, Unit::Year)));
+    }
+
+    #[test]
+    fn recognizes_powers_of_ten_and_repdigits() {
+        assert!(!is_power_of_ten(0));
+        assert!(!is_power_of_ten(1));
+        assert!(is_power_of_ten(10));
+        assert!(!is_power_of_ten(20));
+        assert!(!is_power_of_ten(101));
+        assert!(is_power_of_ten(10_000_000_000_000_000_000));
+
+        assert!(!is_repdigit(0));
+        assert!(!is_repdigit(7));
+        assert!(!is_repdigit(77));
+        assert!(is_repdigit(777));
+        assert!(!is_repdigit(778));
+        assert!(is_repdigit(11_111_111_111_111_111_111));
+    }
+
+    #[test]
+    fn finds_each_count_once() {
+        let start = date(2024, 1, 1);
+        let end = start + chrono::Duration::days(1000);
+        let milestones = find(&[Rule::Multiple(1000), Rule::PowersOfTen, Rule::Multiple(500), Rule::Repdigit], start, end);
+        assert_eq!(milestones, [Milestone { rule: Rule::Multiple(1000), count: 1000, unit: Unit::Day }]);
+
+        let end = start + chrono::Duration::days(700);
+        let milestones = find(&[Rule::Multiple(100), Rule::Weeks], start, end);
+        assert_eq!(milestones.len(), 2);
+        assert_eq!((milestones[1].count, milestones[1].unit), (100, Unit::Week));
+        assert!(find(&DEFAULT_RULES, start, start).is_empty());
+    }
+}
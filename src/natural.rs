@@ -0,0 +1,306 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveDate, Datelike, Weekday};
+use crate::calc::add_days;
+use crate::span::{Span, Unit};
+
+const MONTH_NAMES: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june",
+    "july", "august", "september", "october", "november", "december",
+];
+
+/// The order of the day and the month in dates like `12/24`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum DateOrder {
+    #[default]
+    MonthDay,
+    DayMonth,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseDateOrderError(String);
+
+impl fmt::Display for ParseDateOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Unknown date order '{}' (expected month-day or day-month)", self.0)
+    }
+}
+
+impl std::error::Error for ParseDateOrderError { }
+
+impl FromStr for DateOrder {
+    type Err = ParseDateOrderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "month-day" => Ok(DateOrder::MonthDay),
+            "day-month" => Ok(DateOrder::DayMonth),
+            _ => Err(ParseDateOrderError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for DateOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            DateOrder::MonthDay => write!(f, "month-day"),
+            DateOrder::DayMonth => write!(f, "day-month"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseDateError(String);
+
+impl fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Invalid date '{}' (expected a date like 2026-12-24, tomorrow, next friday, in 3 weeks or dec 24)", self.0)
+    }
+}
+
+impl std::error::Error for ParseDateError { }
+
+/// Parses a date written the way people write them, relative to `today`:
+///
+/// - `2026-12-24`, `24.12.2026`, `24.12.`, `12/24/2026` or `12/24`
+///   (slashed dates in the given order, dotted ones always day first)
+/// - `dec 24`, `24 december` or `december 24, 2026`
+/// - `today`, `tomorrow` or `yesterday`
+/// - `friday` (today or the coming Friday), `next friday` or `last friday`
+/// - `this week`, `next week` or `last week` (the first day of the week,
+///   which starts on `week_start`)
+/// - `next month`, `last year`, `in 3 weeks` or `2 days ago`
+///
+/// A date without a year is its next occurrence, counting today.
+pub fn parse_date(text: &str, today: NaiveDate, order: DateOrder, week_start: Weekday) -> Result<NaiveDate, ParseDateError> {
+    let lowercase = text.trim().to_lowercase();
+    let words: Vec<&str> = lowercase.split_whitespace()
+        .map(|word| word.trim_end_matches(','))
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let date = match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["in", count, unit] => parse_count(count).and_then(|count| add_units(today, count, unit)),
+        [count, unit, "ago"] => parse_count(count)
+            .and_then(i64::checked_neg)
+            .and_then(|count| add_units(today, count, unit)),
+        ["this", "week"] => start_of_week(today, week_start),
+        ["next", "week"] => start_of_week(today, week_start).and_then(|date| add_days(date, 7)),
+        ["last", "week"] => start_of_week(today, week_start).and_then(|date| add_days(date, -7)),
+        ["next", word] => match parse_weekday(word) {
+            Some(weekday) => weekday_after(today, weekday),
+            None => add_units(today, 1, word),
+        },
+        ["last", word] => match parse_weekday(word) {
+            Some(weekday) => weekday_before(today, weekday),
+            None => add_units(today, -1, word),
+        },
+        ["this", word] => parse_weekday(word).and_then(|weekday| weekday_from(today, weekday)),
+        [word] => match parse_weekday(word) {
+            Some(weekday) => weekday_from(today, weekday),
+            None => parse_numeric(word, today, order),
+        },
+        [first, second] => parse_named(first, second, None, today),
+        [first, second, year] => parse_year(year).and_then(|year| parse_named(first, second, Some(year), today)),
+        _ => None,
+    };
+
+    date.ok_or_else(|| ParseDateError(text.trim().to_string()))
+}
+
+/// Parses dates written with numbers only.
+fn parse_numeric(word: &str, today: NaiveDate, order: DateOrder) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    let (parts, order) = if word.contains('.') {
+        (word.trim_end_matches('.').split('.').collect::<Vec<&str>>(), DateOrder::DayMonth)
+    }
+    else if word.contains('/') {
+        (word.split('/').collect::<Vec<&str>>(), order)
+    }
+    else {
+        return None;
+    };
+
+    let (first, second, year) = match parts.as_slice() {
+        [first, second] => (*first, *second, None),
+        [first, second, year] => (*first, *second, Some(parse_year(year)?)),
+        _ => return None,
+    };
+    let (first, second) = (first.parse::<u32>().ok()?, second.parse::<u32>().ok()?);
+    let (month, day) = match order {
+        DateOrder::MonthDay => (first, second),
+        DateOrder::DayMonth => (second, first),
+    };
+    make_date(year, month, day, today)
+}
+
+/// Parses dates with the name of the month, like `dec 24` or `24 dec`.
+fn parse_named(first: &str, second: &str, year: Option<i32>, today: NaiveDate) -> Option<NaiveDate> {
+    let (month, day) = match (parse_month(first), parse_month(second)) {
+        (Some(month), None) => (month, parse_day(second)?),
+        (None, Some(month)) => (month, parse_day(first)?),
+        _ => return None,
+    };
+    make_date(year, month, day, today)
+}
+
+/// Makes the date, or without a year, its next occurrence on
+/// or after today. February 29 waits for the next leap year.
+fn make_date(year: Option<i32>, month: u32, day: u32, today: NaiveDate) -> Option<NaiveDate> {
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+        None => (0..8)
+            .filter_map(|offset| NaiveDate::from_ymd_opt(today.year() + offset, month, day))
+            .find(|date| *date >= today),
+    }
+}
+
+/// Parses a month name, or the start of one with at least three letters.
+fn parse_month(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    if word.len() < 3 {
+        return None;
+    }
+    MONTH_NAMES.iter()
+        .position(|name| name.starts_with(word))
+        .map(|index| index as u32 + 1)
+}
+
+/// Parses a day of the month, like `24` or `24th`.
+fn parse_day(word: &str) -> Option<u32> {
+    let number = word.trim_end_matches('.')
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    number.parse::<u32>().ok()
+}
+
+/// Parses a year, taking two-digit years to be in this century.
+fn parse_year(word: &str) -> Option<i32> {
+    let year = word.parse::<i32>().ok()?;
+    match word.len() {
+        2 => Some(2000 + year),
+        4 => Some(year),
+        _ => None,
+    }
+}
+
+fn parse_count(word: &str) -> Option<i64> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        _ => word.parse::<i64>().ok(),
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    // Chrono accepts both the short and the full names.
+    word.parse::<Weekday>().ok()
+}
+
+fn add_units(date: NaiveDate, count: i64, unit: &str) -> Option<NaiveDate> {
+    let unit = match unit.trim_end_matches('s') {
+        "day" => Unit::Days,
+        "week" => Unit::Weeks,
+        "month" => Unit::Months,
+        "year" => Unit::Years,
+        _ => return None,
+    };
+    Span { count, unit }.add_to(date)
+}
+
+/// Returns the first day of the week the date is in.
+fn start_of_week(date: NaiveDate, week_start: Weekday) -> Option<NaiveDate> {
+    let days = (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    add_days(date, -(days as i64))
+}
+
+/// Returns the date if it is on the weekday, or else the next one that is.
+fn weekday_from(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days = (weekday.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+    add_days(date, days as i64)
+}
+
+/// Returns the first date after this one that is on the weekday.
+fn weekday_after(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    weekday_from(date.succ_opt()?, weekday)
+}
+
+/// Returns the last date before this one that is on the weekday.
+fn weekday_before(date: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    weekday_after(add_days(date, -8)?, weekday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// Parses relative to Wednesday, June 5, 2024, with weeks starting on Monday.
+    fn parse(text: &str) -> Option<NaiveDate> {
+        parse_date(text, date(2024, 6, 5), DateOrder::MonthDay, Weekday::Mon).ok()
+    }
+
+    #[test]
+    fn parses_numeric_dates() {
+        assert_eq!(parse("2026-12-24"), Some(date(2026, 12, 24)));
+        assert_eq!(parse("24.12.2026"), Some(date(2026, 12, 24)));
+        assert_eq!(parse("24.12."), Some(date(2024, 12, 24)));
+        assert_eq!(parse("12/24/26"), Some(date(2026, 12, 24)));
+        assert_eq!(parse("12/24"), Some(date(2024, 12, 24)));
+        assert_eq!(parse_date("24/12", date(2024, 6, 5), DateOrder::DayMonth, Weekday::Mon).ok(), Some(date(2024, 12, 24)));
+        assert_eq!(parse("13/24"), None);
+    }
+
+    #[test]
+    fn parses_month_names() {
+        assert_eq!(parse("dec 24"), Some(date(2024, 12, 24)));
+        assert_eq!(parse("24th December"), Some(date(2024, 12, 24)));
+        assert_eq!(parse("December 24, 2027"), Some(date(2027, 12, 24)));
+        // A date without a year that has passed is next year's.
+        assert_eq!(parse("jan 1"), Some(date(2025, 1, 1)));
+        assert_eq!(parse("june 5"), Some(date(2024, 6, 5)));
+        assert_eq!(parse("feb 29"), Some(date(2028, 2, 29)));
+        assert_eq!(parse("de 24"), None);
+    }
+
+    #[test]
+    fn parses_relative_dates() {
+        assert_eq!(parse("today"), Some(date(2024, 6, 5)));
+        assert_eq!(parse("Tomorrow"), Some(date(2024, 6, 6)));
+        assert_eq!(parse("yesterday"), Some(date(2024, 6, 4)));
+        assert_eq!(parse("wednesday"), Some(date(2024, 6, 5)));
+        assert_eq!(parse("this fri"), Some(date(2024, 6, 7)));
+        assert_eq!(parse("next wednesday"), Some(date(2024, 6, 12)));
+        assert_eq!(parse("last wednesday"), Some(date(2024, 5, 29)));
+        assert_eq!(parse("in 3 weeks"), Some(date(2024, 6, 26)));
+        assert_eq!(parse("a month ago"), Some(date(2024, 5, 5)));
+        assert_eq!(parse("next year"), Some(date(2025, 6, 5)));
+        assert_eq!(parse("in 3 fortnights"), None);
+    }
+
+    #[test]
+    fn weeks_start_on_the_week_start() {
+        assert_eq!(parse("this week"), Some(date(2024, 6, 3)));
+        assert_eq!(parse("next week"), Some(date(2024, 6, 10)));
+        assert_eq!(parse("last week"), Some(date(2024, 5, 27)));
+        let sunday = parse_date("next week", date(2024, 6, 5), DateOrder::MonthDay, Weekday::Sun).ok();
+        assert_eq!(sunday, Some(date(2024, 6, 9)));
+    }
+
+    #[test]
+    fn counts_out_of_range_are_errors() {
+        assert_eq!(parse("in 99999999999999 days"), None);
+        assert_eq!(parse("in 9223372036854775807 weeks"), None);
+        assert_eq!(parse("-9223372036854775808 days ago"), None);
+        assert_eq!(parse("in 99999999999999999999 days"), None);
+        assert_eq!(parse_date("next week", NaiveDate::MAX, DateOrder::MonthDay, Weekday::Mon).ok(), None);
+        assert_eq!(parse_date("tomorrow", NaiveDate::MAX, DateOrder::MonthDay, Weekday::Mon).ok(), None);
+    }
+}
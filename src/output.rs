@@ -0,0 +1,85 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use crate::{EventItem, EventKind};
+use crate::milestone::Milestone;
+
+/// Version of the JSON output. It changes only when fields are removed
+/// or change meaning; new fields can be added within the same version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The whole listing as one JSON document.
+#[derive(Debug, Serialize)]
+pub struct Listing {
+    pub version: u32,
+    /// The reference date the day counts are relative to.
+    pub today: String,
+    pub events: Vec<EventRecord>,
+}
+
+impl Listing {
+    pub fn new(today: NaiveDate, events: Vec<EventRecord>) -> Self {
+        Listing { version: SCHEMA_VERSION, today: today.format(DATE_FORMAT).to_string(), events }
+    }
+}
+
+/// One event in the JSON output. In NDJSON output each line is one
+/// of these, so it carries the schema version too.
+#[derive(Debug, Serialize)]
+pub struct EventRecord {
+    pub version: u32,
+    pub id: String,
+    pub date: String,
+    pub category: String,
+    pub description: String,
+    pub recurrence: Option<String>,
+    /// The events file the event comes from, if several are in use.
+    pub source: Option<String>,
+    pub birthday: bool,
+    /// Days from the reference date to the event, or to its next
+    /// occurrence. Negative for past events.
+    pub days: i64,
+    /// Days to the event counting only business days, if enabled.
+    pub business_days: Option<i64>,
+    /// Next occurrence of a recurring event, if it has not ended.
+    pub next: Option<String>,
+    /// Days from the original date to the reference date.
+    pub since: i64,
+    /// True if the day count is a milestone by any of the rules.
+    pub milestone: bool,
+    pub milestones: Vec<MilestoneRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MilestoneRecord {
+    pub rule: String,
+    pub count: u64,
+    pub unit: String,
+}
+
+impl EventRecord {
+    pub fn new(item: &EventItem, milestones: &[Milestone]) -> Self {
+        let event = &item.event;
+        EventRecord {
+            version: SCHEMA_VERSION,
+            id: event.id(),
+            date: event.date.format(DATE_FORMAT).to_string(),
+            category: event.category.clone(),
+            description: event.description.clone(),
+            recurrence: event.recurrence.map(|recurrence| recurrence.to_string()),
+            source: item.source.clone(),
+            birthday: event.kind() == EventKind::Birthday,
+            days: item.days,
+            business_days: item.business_days,
+            next: item.next.map(|next| next.format(DATE_FORMAT).to_string()),
+            since: item.since,
+            milestone: !milestones.is_empty(),
+            milestones: milestones.iter().map(|milestone| MilestoneRecord {
+                rule: milestone.rule.to_string(),
+                count: milestone.count,
+                unit: milestone.unit.to_string(),
+            }).collect(),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveDate, Datelike, Weekday, Months};
+use crate::calc::add_days;
+
+/// The longest interval of `every:<days>`, about a hundred years.
+pub const MAX_EVERY_DAYS: u32 = 36525;
+
+/// How often a recurring event repeats.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Frequency {
+    /// Every year on the month and day of the original date.
+    Yearly,
+    /// Every month on the given day (clamped to the length of the month).
+    Monthly(u32),
+    /// Every week on the given weekday.
+    Weekly(Weekday),
+    /// Every N days counting from the original date.
+    EveryDays(u32),
+}
+
+impl Frequency {
+    // `Weekday` does not implement `Ord`, so order by a numeric key instead.
+    fn sort_key(&self) -> (u8, u32) {
+        match self {
+            Frequency::Yearly => (0, 0),
+            Frequency::Monthly(day) => (1, *day),
+            Frequency::Weekly(weekday) => (2, weekday.num_days_from_monday()),
+            Frequency::EveryDays(count) => (3, *count),
+        }
+    }
+}
+
+impl Ord for Frequency {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for Frequency {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A recurrence rule, stored in the events file as text like
+/// `yearly`, `monthly:15`, `weekly:fri` or `every:10`, optionally
+/// followed by an end date: `weekly:mon;until=2026-06-30`.
+#[derive(Debug, Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub until: Option<NaiveDate>,
+}
+
+impl Recurrence {
+    /// Returns the first occurrence on or after `from` of an event
+    /// that started on `start`, or `None` if the recurrence has ended
+    /// or the next occurrence would be out of the range of dates.
+    pub fn next_occurrence(&self, start: NaiveDate, from: NaiveDate) -> Option<NaiveDate> {
+        let from = if from < start { start } else { from };
+
+        let next = match self.frequency {
+            Frequency::Yearly => {
+                let mut year = from.year();
+                loop {
+                    let date = yearly_date(start, year)?;
+                    if date >= from {
+                        break date;
+                    }
+                    year += 1;
+                }
+            },
+            Frequency::Monthly(day) => {
+                let mut month = NaiveDate::from_ymd_opt(from.year(), from.month(), 1)?;
+                loop {
+                    let date = clamped_date(month.year(), month.month(), day)?;
+                    if date >= from {
+                        break date;
+                    }
+                    month = month.checked_add_months(Months::new(1))?;
+                }
+            },
+            Frequency::Weekly(weekday) => {
+                let ahead = (7 + weekday.num_days_from_monday()
+                    - from.weekday().num_days_from_monday()) % 7;
+                add_days(from, ahead as i64)?
+            },
+            Frequency::EveryDays(count) => {
+                let count = count as i64;
+                let elapsed = from.signed_duration_since(start).num_days();
+                let periods = (elapsed + count - 1) / count;
+                add_days(start, periods.checked_mul(count)?)?
+            },
+        };
+
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+}
+
+/// Returns the anniversary of `start` in the given year.
+/// February 29 falls on February 28 in common years.
+pub fn yearly_date(start: NaiveDate, year: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, start.month(), start.day())
+        .or_else(|| NaiveDate::from_ymd_opt(year, start.month(), start.day() - 1))
+}
+
+/// Returns the given day of the month, or the last day of the month
+/// if the month is shorter than that.
+fn clamped_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day).rev().find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseRecurrenceError(String);
+
+impl fmt::Display for ParseRecurrenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Invalid recurrence rule '{}' \
+            (expected yearly, monthly:<day>, weekly:<weekday> or every:<days> \
+            with at most {} days, optionally followed by ;until=<YYYY-MM-DD>)", self.0, MAX_EVERY_DAYS)
+    }
+}
+
+impl std::error::Error for ParseRecurrenceError { }
+
+impl FromStr for Recurrence {
+    type Err = ParseRecurrenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseRecurrenceError(s.to_string());
+
+        let (rule, until) = match s.trim().split_once(';') {
+            Some((rule, end)) => {
+                let date = end.trim().strip_prefix("until=").ok_or_else(error)?;
+                let until = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| error())?;
+                (rule, Some(until))
+            },
+            None => (s.trim(), None),
+        };
+
+        let rule = rule.to_lowercase();
+        let frequency = match rule.split_once(':') {
+            None if rule == "yearly" => Frequency::Yearly,
+            Some(("monthly", day)) => {
+                match day.parse::<u32>() {
+                    Ok(day) if (1..=31).contains(&day) => Frequency::Monthly(day),
+                    _ => return Err(error()),
+                }
+            },
+            Some(("weekly", weekday)) => {
+                Frequency::Weekly(weekday.parse::<Weekday>().map_err(|_| error())?)
+            },
+            Some(("every", count)) => {
+                match count.trim_end_matches('d').parse::<u32>() {
+                    Ok(count) if (1..=MAX_EVERY_DAYS).contains(&count) => Frequency::EveryDays(count),
+                    _ => return Err(error()),
+                }
+            },
+            _ => return Err(error()),
+        };
+
+        Ok(Recurrence { frequency, until })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self.frequency {
+            Frequency::Yearly => write!(f, "yearly")?,
+            Frequency::Monthly(day) => write!(f, "monthly:{}", day)?,
+            Frequency::Weekly(weekday) => write!(f, "weekly:{}", weekday.to_string().to_lowercase())?,
+            Frequency::EveryDays(count) => write!(f, "every:{}", count)?,
+        }
+        if let Some(until) = self.until {
+            write!(f, ";until={}", until)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn rule(s: &str) -> Recurrence {
+        s.parse::<Recurrence>().unwrap()
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(rule("yearly").frequency, Frequency::Yearly);
+        assert_eq!(rule("Monthly:31").frequency, Frequency::Monthly(31));
+        assert_eq!(rule("weekly:fri").frequency, Frequency::Weekly(Weekday::Fri));
+        assert_eq!(rule("every:10d").frequency, Frequency::EveryDays(10));
+        assert_eq!(rule("weekly:mon;until=2026-06-30").until, Some(date(2026, 6, 30)));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for s in ["", "daily", "monthly:0", "monthly:32", "weekly:funday", "every:0",
+            "every:36526", "every:4294967295", "yearly;until=never", "yearly;since=2024-01-01"] {
+            assert!(s.parse::<Recurrence>().is_err(), "{}", s);
+        }
+        assert_eq!(rule("every:36525").frequency, Frequency::EveryDays(MAX_EVERY_DAYS));
+    }
+
+    #[test]
+    fn displays_rules_the_way_they_are_parsed() {
+        for s in ["yearly", "monthly:15", "weekly:fri", "every:10", "weekly:mon;until=2026-06-30"] {
+            assert_eq!(rule(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn finds_the_next_occurrence() {
+        let start = date(2024, 1, 31);
+        assert_eq!(rule("yearly").next_occurrence(start, date(2024, 6, 1)), Some(date(2025, 1, 31)));
+        assert_eq!(rule("monthly:31").next_occurrence(start, date(2024, 2, 1)), Some(date(2024, 2, 29)));
+        assert_eq!(rule("weekly:fri").next_occurrence(start, date(2024, 2, 2)), Some(date(2024, 2, 2)));
+        assert_eq!(rule("every:10").next_occurrence(start, date(2024, 2, 1)), Some(date(2024, 2, 10)));
+        // Before the start, the first occurrence is the start itself.
+        assert_eq!(rule("every:10").next_occurrence(start, date(2023, 1, 1)), Some(start));
+    }
+
+    #[test]
+    fn leap_day_falls_on_february_28() {
+        let start = date(2024, 2, 29);
+        assert_eq!(rule("yearly").next_occurrence(start, date(2024, 3, 1)), Some(date(2025, 2, 28)));
+        assert_eq!(rule("yearly").next_occurrence(start, date(2027, 3, 1)), Some(date(2028, 2, 29)));
+    }
+
+    #[test]
+    fn ends_on_the_until_date() {
+        let start = date(2024, 1, 1);
+        let recurrence = rule("weekly:mon;until=2024-01-15");
+        assert_eq!(recurrence.next_occurrence(start, date(2024, 1, 15)), Some(date(2024, 1, 15)));
+        assert_eq!(recurrence.next_occurrence(start, date(2024, 1, 16)), None);
+    }
+
+    #[test]
+    fn occurrences_past_the_last_date_are_none() {
+        let last = NaiveDate::MAX;
+        let start = date(2024, 1, 1);
+        assert_eq!(rule("yearly").next_occurrence(start, last), None);
+        assert_eq!(rule("monthly:15").next_occurrence(start, last), None);
+        let weekly = Recurrence { frequency: Frequency::Weekly(last.weekday().succ()), until: None };
+        assert_eq!(weekly.next_occurrence(start, last), None);
+        assert_eq!(rule("every:36525").next_occurrence(start, last), None);
+        assert_eq!(rule("every:36525").next_occurrence(NaiveDate::MIN, last), None);
+    }
+}
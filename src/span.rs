@@ -0,0 +1,143 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveDate, Months};
+use crate::calc::add_days;
+
+/// The unit of a span of time.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Unit {
+    Days,
+    Weeks,
+    Months,
+    Years,
+}
+
+/// A span of time like `30d`, `2w`, `3m` or `1y`.
+/// A number without a unit is in days.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Span {
+    pub count: i64,
+    pub unit: Unit,
+}
+
+impl Span {
+    /// Returns the date this span after `date` (or before it, if the
+    /// count is negative). Months and years are calendar months and
+    /// years, clamped to the end of a shorter month. Returns `None` if
+    /// the date would be out of range.
+    pub fn add_to(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.unit {
+            Unit::Days => add_days(date, self.count),
+            Unit::Weeks => add_days(date, self.count.checked_mul(7)?),
+            Unit::Months => add_months(date, self.count),
+            Unit::Years => add_months(date, self.count.checked_mul(12)?),
+        }
+    }
+
+    /// Returns the span in the other direction, or `None` for the
+    /// one count that has no opposite.
+    pub fn negated(&self) -> Option<Span> {
+        Some(Span { count: self.count.checked_neg()?, unit: self.unit })
+    }
+}
+
+fn add_months(date: NaiveDate, count: i64) -> Option<NaiveDate> {
+    let months = Months::new(u32::try_from(count.unsigned_abs()).ok()?);
+    if count >= 0 {
+        date.checked_add_months(months)
+    }
+    else {
+        date.checked_sub_months(months)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseSpanError(String);
+
+impl fmt::Display for ParseSpanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Invalid span '{}' (expected a number followed by d, w, m or y, like 30d)", self.0)
+    }
+}
+
+impl std::error::Error for ParseSpanError { }
+
+impl FromStr for Span {
+    type Err = ParseSpanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseSpanError(s.to_string());
+
+        let text = s.trim().to_lowercase();
+        let (number, unit) = match text.char_indices().last() {
+            Some((index, 'd')) => (&text[..index], Unit::Days),
+            Some((index, 'w')) => (&text[..index], Unit::Weeks),
+            Some((index, 'm')) => (&text[..index], Unit::Months),
+            Some((index, 'y')) => (&text[..index], Unit::Years),
+            Some(_) => (text.as_str(), Unit::Days),
+            None => return Err(error()),
+        };
+        let number = number.strip_prefix('+').unwrap_or(number);
+        let count = number.parse::<i64>().map_err(|_| error())?;
+        Ok(Span { count, unit })
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let unit = match self.unit {
+            Unit::Days => 'd',
+            Unit::Weeks => 'w',
+            Unit::Months => 'm',
+            Unit::Years => 'y',
+        };
+        write!(f, "{}{}", self.count, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn span(s: &str) -> Span {
+        s.parse::<Span>().unwrap()
+    }
+
+    #[test]
+    fn parses_spans() {
+        assert_eq!(span("30"), Span { count: 30, unit: Unit::Days });
+        assert_eq!(span("+2W"), Span { count: 2, unit: Unit::Weeks });
+        assert_eq!(span("-3m"), Span { count: -3, unit: Unit::Months });
+        assert!("y".parse::<Span>().is_err());
+        assert!("3x".parse::<Span>().is_err());
+    }
+
+    #[test]
+    fn adds_spans_to_dates() {
+        let start = date(2024, 1, 31);
+        assert_eq!(span("1d").add_to(start), Some(date(2024, 2, 1)));
+        assert_eq!(span("-1w").add_to(start), Some(date(2024, 1, 24)));
+        assert_eq!(span("1m").add_to(start), Some(date(2024, 2, 29)));
+        assert_eq!(span("1y").add_to(date(2024, 2, 29)), Some(date(2025, 2, 28)));
+    }
+
+    #[test]
+    fn spans_out_of_range_are_none() {
+        let start = date(2024, 1, 1);
+        assert_eq!(span("999999999999999d").add_to(start), None);
+        assert_eq!(span("9223372036854775807w").add_to(start), None);
+        assert_eq!(span("-9223372036854775808d").add_to(start), None);
+        assert_eq!(span("9223372036854775807y").add_to(start), None);
+        assert_eq!(span("1d").add_to(NaiveDate::MAX), None);
+    }
+
+    #[test]
+    fn negates_spans() {
+        assert_eq!(span("3m").negated(), Some(span("-3m")));
+        assert_eq!(span("-9223372036854775808d").negated(), None);
+    }
+}
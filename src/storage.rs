@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::{Event, DaysError};
+use crate::backup::create_backup;
+use crate::store::{open_store, Format, Loaded};
+
+/// Name of the default events file in the days directory,
+/// without the extension of its format.
+pub const EVENTS_FILE_STEM: &str = "events";
+
+/// Returns the paths of the events files to use. Files given on the
+/// command line take precedence over the `DAYS_FILE` environment variable
+/// (a list of paths like `PATH`), and if neither is set, the events file
+/// in the days directory is used, like `events.csv` if the format is CSV.
+/// The first file is the one new events go to.
+pub fn get_events_paths(files: Vec<PathBuf>, format: Format) -> Result<Vec<PathBuf>, DaysError> {
+    if !files.is_empty() {
+        return Ok(files);
+    }
+
+    if let Some(value) = env::var_os("DAYS_FILE") {
+        let paths: Vec<PathBuf> = env::split_paths(&value)
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect();
+        if !paths.is_empty() {
+            return Ok(paths);
+        }
+    }
+
+    Ok(vec![get_events_path(format)?])
+}
+
+/// Returns short names for the events files, shown in listings that
+/// merge several files. The names are the file names without their
+/// extensions, unless that makes two of them the same, in which case
+/// they are the shortest ends of the paths that tell them apart.
+pub fn get_source_names(paths: &[PathBuf]) -> Vec<String> {
+    let stems: Vec<String> = paths.iter()
+        .map(|path| match path.file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => path.display().to_string(),
+        })
+        .collect();
+    if all_different(&stems) {
+        return stems;
+    }
+
+    let longest = paths.iter().map(|path| path.components().count()).max().unwrap_or(0);
+    for depth in 1..=longest {
+        let names: Vec<String> = paths.iter().map(|path| path_end(path, depth)).collect();
+        if all_different(&names) {
+            return names;
+        }
+    }
+    paths.iter().map(|path| path.display().to_string()).collect()
+}
+
+/// Returns the last components of the path, at most `depth` of them.
+fn path_end(path: &Path, depth: usize) -> String {
+    let components: Vec<_> = path.components().collect();
+    let start = components.len().saturating_sub(depth);
+    components[start..].iter().collect::<PathBuf>().display().to_string()
+}
+
+fn all_different(names: &[String]) -> bool {
+    names.iter().collect::<HashSet<&String>>().len() == names.len()
+}
+
+/// Returns the path of the events file in the days directory, like
+/// `events.csv` for CSV, creating the directory if it does not exist yet.
+pub fn get_events_path(format: Format) -> Result<PathBuf, DaysError> {
+    if let Some(path) = get_days_path() {
+        // Create the working directory if it does not exist.
+        if !Path::exists(path.as_path()) {
+            if let Err(source) = std::fs::create_dir_all(path.as_path()) {
+                return Err(DaysError::CreateError { path, source });
+            }
+        }
+        else if !path.is_dir() {
+            return Err(DaysError::WorkingDirectoryNotFound(path));
+        }
+
+        let mut events_path = path.clone();
+        events_path.push(format!("{}.{}", EVENTS_FILE_STEM, format.extension()));
+        Ok(events_path)
+    }
+    else {
+        Err(DaysError::HomeDirectoryNotFound)
+    }
+}
+
+/// Resolves a full or abbreviated event identifier.
+/// Events with identical contents share the same identifier,
+/// so a match on several of those is not considered ambiguous.
+pub fn find_event_id<'a>(events: impl Iterator<Item = &'a Event>, prefix: &str) -> Result<String, DaysError> {
+    let mut ids: Vec<String> = events
+        .map(|event| event.id())
+        .filter(|id| id.starts_with(&prefix.to_lowercase()))
+        .collect();
+    ids.sort();
+    ids.dedup();
+
+    match ids.len() {
+        0 => Err(DaysError::EventNotFound(prefix.to_string())),
+        1 => Ok(ids.remove(0)),
+        _ => Err(DaysError::AmbiguousId { prefix: prefix.to_string(), matches: ids }),
+    }
+}
+
+/// Reads the events from the file, or returns an empty list
+/// if the file does not exist yet.
+pub fn load_events(events_path: &Path, mode: ReadMode) -> Result<Loaded, DaysError> {
+    if !events_path.exists() {
+        return Ok(Loaded::default());
+    }
+    open_store(events_path)?.load(mode)
+}
+
+/// Reads the events from each file, keeping track of which file they came from.
+pub fn load_event_files(events_paths: &[PathBuf], mode: ReadMode) -> Result<Vec<(&Path, Loaded)>, DaysError> {
+    let mut files = Vec::new();
+    for events_path in events_paths.iter() {
+        files.push((events_path.as_path(), load_events(events_path, mode)?));
+    }
+    Ok(files)
+}
+
+/// Reads the events from all the files into one list.
+pub fn load_all_events(events_paths: &[PathBuf], mode: ReadMode) -> Result<Loaded, DaysError> {
+    let mut loaded = Loaded::default();
+    for events_path in events_paths.iter() {
+        loaded.extend(load_events(events_path, mode)?);
+    }
+    Ok(loaded)
+}
+
+/// Writes the events to the file, in the format its extension says,
+/// after making a backup of the previous version.
+pub fn save_events(events: Vec<Event>, events_path: &Path, backups: usize) -> Result<(), DaysError> {
+    let store = open_store(events_path)?;
+    store.check_writable()?;
+    if backups > 0 {
+        create_backup(events_path, backups)?;
+    }
+    store.write(&events)
+}
+
+/// Upgrades the events file to the current layout of its format, after
+/// making a backup. Returns the layout the file was in, or `None` if it
+/// was already up to date or does not exist. Rows that are not valid
+/// events stop the migration, so that nothing is lost.
+pub fn migrate_events(events_path: &Path, backups: usize) -> Result<Option<String>, DaysError> {
+    if !events_path.exists() {
+        return Ok(None);
+    }
+
+    let store = open_store(events_path)?;
+    let layout = match store.outdated_layout()? {
+        Some(layout) => layout,
+        None => return Ok(None),
+    };
+    // Unknown columns are the only warnings in strict mode,
+    // and they stop the migration below.
+    let events = store.load(ReadMode::Strict)?.events;
+    store.check_writable()?;
+    // The backup is made even if backups are otherwise turned off.
+    create_backup(events_path, backups.max(1))?;
+    store.write(&events)?;
+    Ok(Some(layout))
+}
+
+/// How to deal with rows that cannot be read as events.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ReadMode {
+    /// Skip bad rows with a warning.
+    #[default]
+    Lenient,
+    /// Fail on the first bad row.
+    Strict,
+}
+
+/// Replaces the contents of the file so that a crash never leaves it
+/// half written: the new contents go to a temporary file in the same
+/// directory, which is then renamed over the original.
+pub(crate) fn replace_file(path: &Path, contents: &[u8]) -> Result<(), DaysError> {
+    let file_name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => EVENTS_FILE_STEM.to_string(),
+    };
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = write_temp_file(&temp_path, path, contents)
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(source) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(DaysError::WriteError { path: path.to_path_buf(), source });
+    }
+
+    // Make the rename itself durable. Not all platforms can open
+    // a directory for this, so errors are ignored.
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        let _ = File::open(parent).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+/// Writes the temporary file with the same permissions as the original.
+fn write_temp_file(temp_path: &Path, original: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(temp_path)?;
+    file.write_all(contents)?;
+    if let Ok(metadata) = fs::metadata(original) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.sync_all()
+}
+
+// See https://blog.liw.fi/posts/2021/10/12/tilde-expansion-crates/ for notes.
+
+/// Returns the days directory: the `DAYS_DIR` environment variable if set,
+/// otherwise `~/.days` if it exists, otherwise `days` in the XDG data
+/// directory (like `~/.local/share/days`), falling back to `~/.days`.
+pub fn get_days_path() -> Option<PathBuf> {
+    if let Some(value) = env::var_os("DAYS_DIR") {
+        if !value.is_empty() {
+            return Some(PathBuf::from(value));
+        }
+    }
+
+    // NOTE: Don't use std::env::home_dir to get the home directory,
+    // it doesn't work like it should! Use the `dirs` crate instead.
+    match dirs::home_dir() {
+        Some(home_dir) => {
+            let mut path = home_dir.clone();
+            // Construct a path for the `~/.days` directory:
+            path.push(".days");
+            if path.exists() {
+                return Some(path);
+            }
+
+            match dirs::data_dir() {
+                Some(mut data_dir) => {
+                    data_dir.push("days");
+                    Some(data_dir)
+                },
+                None => Some(path),
+            }
+        },
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(paths: &[&str]) -> Vec<String> {
+        get_source_names(&paths.iter().map(PathBuf::from).collect::<Vec<PathBuf>>())
+    }
+
+    #[test]
+    fn source_names_are_file_stems_when_they_differ() {
+        assert_eq!(names(&["/home/me/work.csv", "/home/me/family.json"]), ["work", "family"]);
+    }
+
+    #[test]
+    fn source_names_tell_the_files_apart() {
+        assert_eq!(names(&["/home/me/events.csv", "/home/me/events.json"]), ["events.csv", "events.json"]);
+        assert_eq!(names(&["/home/me/work/events.csv", "/home/me/family/events.csv"]),
+            ["work/events.csv", "family/events.csv"]);
+        assert_eq!(names(&["a/events.csv", "a/events.csv"]), ["a/events.csv", "a/events.csv"]);
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use serde::de::IgnoredAny;
+use crate::{Event, DaysError};
+use crate::check::ProblemKind;
+use crate::recurrence::Recurrence;
+use crate::storage::ReadMode;
+
+mod csv_store;
+mod json_store;
+mod toml_store;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+
+pub use csv_store::{CsvStore, CsvLayout, CURRENT_LAYOUT};
+pub use json_store::JsonStore;
+pub use toml_store::TomlStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite_store::SqliteStore;
+
+/// One entry in an events file: the event, or the reason it is not one.
+/// The position is the line number in text files, or the number of the
+/// entry in formats without meaningful lines. Position 0 is the
+/// file as a whole.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub position: u64,
+    pub event: Result<Event, ProblemKind>,
+}
+
+/// An entry that was skipped when loading, because it is not a valid
+/// event, or a problem that was ignored, like an unknown column.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Warning {
+    pub path: PathBuf,
+    pub position: u64,
+    pub problem: ProblemKind,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.position == 0 {
+            write!(f, "{}: warning: {} (ignored)", self.path.display(), self.problem)
+        }
+        else if self.problem.is_fatal() {
+            write!(f, "{}:{}: {} (skipped)", self.path.display(), self.position, self.problem)
+        }
+        else {
+            write!(f, "{}:{}: warning: {} (ignored)", self.path.display(), self.position, self.problem)
+        }
+    }
+}
+
+/// The events loaded from one or more stores, with warnings about
+/// the entries that could not be loaded, for the caller to show.
+#[derive(Debug, Clone, Default)]
+pub struct Loaded {
+    pub events: Vec<Event>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Loaded {
+    /// Adds the events and warnings of another load to these.
+    pub fn extend(&mut self, other: Loaded) {
+        self.events.extend(other.events);
+        self.warnings.extend(other.warnings);
+    }
+}
+
+/// A place where events are kept.
+pub trait EventStore {
+    fn path(&self) -> &Path;
+
+    /// Reads every entry, including the ones that are not valid events.
+    /// The store must exist.
+    fn read(&self) -> Result<Vec<Entry>, DaysError>;
+
+    /// Replaces all the events in the store with these.
+    fn write(&self, events: &[Event]) -> Result<(), DaysError>;
+
+    /// Fails if writing the store would lose something that is in it,
+    /// like columns that `days` does not know.
+    fn check_writable(&self) -> Result<(), DaysError> {
+        Ok(())
+    }
+
+    /// Describes the older layout the store is in, or returns `None`
+    /// if it is in the current one and there is nothing to migrate.
+    fn outdated_layout(&self) -> Result<Option<String>, DaysError> {
+        Ok(None)
+    }
+
+    /// Reads the events. Entries that are not valid events are
+    /// skipped with a warning, or in strict mode, are an error.
+    fn load(&self, mode: ReadMode) -> Result<Loaded, DaysError> {
+        let mut loaded = Loaded::default();
+        for entry in self.read()?.into_iter() {
+            match (entry.event, mode) {
+                (Ok(event), _) => loaded.events.push(event),
+                // Unknown columns are only a problem when writing.
+                (Err(problem), _) if !problem.is_fatal() => {
+                    loaded.warnings.push(Warning { path: self.path().to_path_buf(), position: entry.position, problem });
+                },
+                (Err(problem), ReadMode::Strict) => {
+                    return Err(DaysError::InvalidEvent {
+                        path: self.path().to_path_buf(),
+                        line: entry.position,
+                        problem,
+                    });
+                },
+                (Err(problem), ReadMode::Lenient) => {
+                    loaded.warnings.push(Warning { path: self.path().to_path_buf(), position: entry.position, problem });
+                },
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+/// The file formats events can be kept in.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Format {
+    #[default]
+    Csv,
+    Json,
+    Toml,
+    Sqlite,
+}
+
+impl Format {
+    /// Chooses the format by the extension of the file.
+    /// Files with other extensions are CSV.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "json" => Format::Json,
+            "toml" => Format::Toml,
+            "sqlite" | "sqlite3" | "db" => Format::Sqlite,
+            _ => Format::Csv,
+        }
+    }
+
+    /// The extension of the default events file in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Sqlite => "sqlite",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseFormatError(String);
+
+impl fmt::Display for ParseFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Unknown format '{}' (expected csv, json, toml or sqlite)", self.0)
+    }
+}
+
+impl std::error::Error for ParseFormatError { }
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "sqlite" => Ok(Format::Sqlite),
+            _ => Err(ParseFormatError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Opens the store for an events file, in the format its extension says.
+pub fn open_store(path: &Path) -> Result<Box<dyn EventStore>, DaysError> {
+    match Format::from_path(path) {
+        Format::Csv => Ok(Box::new(CsvStore::new(path))),
+        Format::Json => Ok(Box::new(JsonStore::new(path))),
+        Format::Toml => Ok(Box::new(TomlStore::new(path))),
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite => Ok(Box::new(SqliteStore::new(path))),
+        #[cfg(not(feature = "sqlite"))]
+        Format::Sqlite => Err(DaysError::UnsupportedFormat(Format::Sqlite)),
+    }
+}
+
+/// Makes an event from its fields as they are stored.
+pub(crate) fn parse_fields(date: &str, category: &str, description: &str, recurrence: Option<&str>) -> Result<Event, ProblemKind> {
+    let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => date,
+        Err(_) => return Err(ProblemKind::InvalidDate(date.to_string())),
+    };
+    let recurrence = match recurrence.map(str::trim) {
+        None | Some("") => None,
+        Some(value) => match value.parse::<Recurrence>() {
+            Ok(recurrence) => Some(recurrence),
+            Err(err) => return Err(ProblemKind::InvalidRecurrence(err.to_string())),
+        },
+    };
+
+    Ok(Event {
+        date,
+        category: category.to_string(),
+        description: description.to_string(),
+        recurrence,
+    })
+}
+
+/// Events files in JSON and TOML have the events in a list under `events`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct Document {
+    #[serde(default)]
+    pub events: Vec<StoredEvent>,
+    /// Keys that `days` does not know. They are never written, so
+    /// a file that has them can't be changed.
+    #[serde(flatten, skip_serializing)]
+    pub extra: BTreeMap<String, IgnoredAny>,
+}
+
+/// An event with its fields as text, like in the CSV files, so that
+/// a bad date or rule can be reported like a bad row in a CSV file.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct StoredEvent {
+    pub date: String,
+    pub category: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    #[serde(flatten, skip_serializing)]
+    pub extra: BTreeMap<String, IgnoredAny>,
+}
+
+impl Document {
+    pub fn new(events: &[Event]) -> Self {
+        let events = events.iter().map(|event| StoredEvent {
+            date: event.date.to_string(),
+            category: event.category.clone(),
+            description: event.description.clone(),
+            recurrence: event.recurrence.map(|recurrence| recurrence.to_string()),
+            extra: BTreeMap::new(),
+        }).collect();
+        Document { events, extra: BTreeMap::new() }
+    }
+
+    /// Returns the entries, with the unknown keys of the document
+    /// at position 0 and those of each event at its position.
+    pub fn entries(&self) -> Vec<Entry> {
+        let unknown = |position: u64, extra: &BTreeMap<String, IgnoredAny>| extra.keys()
+            .map(|name| Entry { position, event: Err(ProblemKind::UnknownField(name.clone())) })
+            .collect::<Vec<Entry>>();
+
+        let mut entries = unknown(0, &self.extra);
+        for (index, stored) in self.events.iter().enumerate() {
+            let position = index as u64 + 1;
+            entries.extend(unknown(position, &stored.extra));
+            entries.push(Entry {
+                position,
+                event: parse_fields(&stored.date, &stored.category, &stored.description, stored.recurrence.as_deref()),
+            });
+        }
+        entries
+    }
+
+    /// Fails if the document has keys that writing it would lose.
+    pub fn check_writable(&self, path: &Path) -> Result<(), DaysError> {
+        let mut columns: Vec<String> = self.extra.keys().cloned().collect();
+        for name in self.events.iter().flat_map(|stored| stored.extra.keys()) {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+        if columns.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(DaysError::UnknownColumns { path: path.to_path_buf(), columns })
+        }
+    }
+}
+
+/// Files for the tests of the stores, removed when they are dropped.
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    pub struct TempFile(PathBuf);
+
+    impl TempFile {
+        /// Writes the contents to a file of its own in the temporary directory.
+        pub fn new(name: &str, contents: &str) -> Self {
+            let path = env::temp_dir().join(format!("days-test-{}-{}", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.0
+        }
+
+        pub fn contents(&self) -> String {
+            fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+}
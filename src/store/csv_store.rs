@@ -0,0 +1,326 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use chrono::NaiveDate;
+use csv::{Writer, ReaderBuilder, StringRecord};
+use crate::{Event, DaysError};
+use crate::check::ProblemKind;
+use crate::storage::replace_file;
+use super::{Entry, EventStore, parse_fields};
+
+/// The columns of the current layout, in the order they are written.
+pub const CURRENT_COLUMNS: [&str; 4] = ["date", "category", "description", "recurrence"];
+
+/// The layouts of CSV events files over time. Files in older layouts
+/// can still be read, and `days migrate` upgrades them to the current one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CsvLayout {
+    /// No header row, with the columns date, category and description,
+    /// and possibly recurrence, in that order.
+    Headerless,
+    /// The header `date,category,description`.
+    V1,
+    /// The header `date,category,description,recurrence`, in any order.
+    V2,
+}
+
+/// The current layout, the one files are written in.
+pub const CURRENT_LAYOUT: CsvLayout = CsvLayout::V2;
+
+impl fmt::Display for CsvLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            CsvLayout::Headerless => write!(f, "the layout without a header"),
+            CsvLayout::V1 => write!(f, "version 1 (without recurrence)"),
+            CsvLayout::V2 => write!(f, "version 2 (with recurrence)"),
+        }
+    }
+}
+
+/// Where each known column is in the rows of a file.
+#[derive(Debug, Clone)]
+struct Columns {
+    layout: CsvLayout,
+    date: usize,
+    category: usize,
+    description: usize,
+    recurrence: Option<usize>,
+    /// Names of the columns that `days` does not know.
+    unknown: Vec<String>,
+    width: usize,
+}
+
+impl Columns {
+    /// Columns by position, for files without a header.
+    fn positional() -> Self {
+        Columns {
+            layout: CsvLayout::Headerless,
+            date: 0,
+            category: 1,
+            description: 2,
+            recurrence: Some(3),
+            unknown: Vec::new(),
+            width: 4,
+        }
+    }
+
+    /// Columns by name, ignoring case and surrounding spaces.
+    fn from_header(header: &StringRecord, path: &Path) -> Result<Self, DaysError> {
+        let names: Vec<String> = header.iter().map(|name| name.trim().to_lowercase()).collect();
+        let find = |name: &str| names.iter().position(|column| column == name);
+        let require = |name: &str| match find(name) {
+            Some(index) => Ok(index),
+            None => Err(DaysError::FormatError {
+                path: path.to_path_buf(),
+                message: format!("the header has no '{}' column", name),
+            }),
+        };
+
+        let recurrence = find("recurrence");
+        Ok(Columns {
+            layout: if recurrence.is_some() { CsvLayout::V2 } else { CsvLayout::V1 },
+            date: require("date")?,
+            category: require("category")?,
+            description: require("description")?,
+            recurrence,
+            unknown: names.iter()
+                .filter(|name| !CURRENT_COLUMNS.contains(&name.as_str()))
+                .cloned()
+                .collect(),
+            width: names.len(),
+        })
+    }
+
+    /// Turns a row into an event. A row may leave out optional
+    /// columns at the end, which is how rows were written before
+    /// recurrence rules existed.
+    fn parse(&self, record: &StringRecord) -> Result<Event, ProblemKind> {
+        let min = [self.date, self.category, self.description].into_iter().max().unwrap_or(0) + 1;
+        if record.len() < min || record.len() > self.width {
+            return Err(ProblemKind::ColumnCount { min, max: self.width, found: record.len() });
+        }
+        parse_fields(
+            &record[self.date],
+            &record[self.category],
+            &record[self.description],
+            self.recurrence.and_then(|index| record.get(index)),
+        )
+    }
+}
+
+/// A header row is recognized by one of its fields being `date`, so
+/// that a bad date in the first row of a file without a header is
+/// reported as a bad row. A row starting with a date is an event,
+/// whatever its other fields are.
+fn is_header(record: &StringRecord) -> bool {
+    if let Some(first) = record.get(0) {
+        if NaiveDate::parse_from_str(first.trim(), "%Y-%m-%d").is_ok() {
+            return false;
+        }
+    }
+    record.iter().any(|field| field.trim().to_lowercase() == "date")
+}
+
+/// Events in a CSV file with the columns date, category, description
+/// and recurrence, and a header row.
+#[derive(Debug, Clone)]
+pub struct CsvStore {
+    path: PathBuf,
+}
+
+impl CsvStore {
+    pub fn new(path: &Path) -> Self {
+        CsvStore { path: path.to_path_buf() }
+    }
+
+    /// Reads the first row of the file to find out its layout.
+    pub fn layout(&self) -> Result<CsvLayout, DaysError> {
+        match self.first_row()? {
+            Some(record) if is_header(&record) => Ok(Columns::from_header(&record, &self.path)?.layout),
+            Some(_) => Ok(CsvLayout::Headerless),
+            // An empty file has nothing to upgrade.
+            None => Ok(CURRENT_LAYOUT),
+        }
+    }
+
+    fn first_row(&self) -> Result<Option<StringRecord>, DaysError> {
+        let path = self.path.as_path();
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|err| csv_error(err, path, false))?;
+        match reader.records().next() {
+            Some(Ok(record)) => Ok(Some(record)),
+            Some(Err(err)) => Err(csv_error(err, path, false)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl EventStore for CsvStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<Vec<Entry>, DaysError> {
+        let path = self.path.as_path();
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|err| csv_error(err, path, false))?;
+
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut columns: Option<Columns> = None;
+        for result in reader.records() {
+            let record = match result {
+                Ok(record) => record,
+                Err(err) if err.is_io_error() => return Err(csv_error(err, path, false)),
+                Err(err) => {
+                    entries.push(Entry {
+                        position: err.position().map_or(0, |position| position.line()),
+                        event: Err(ProblemKind::Malformed(err.to_string())),
+                    });
+                    continue;
+                },
+            };
+            let position = record.position().map_or(0, |position| position.line());
+
+            let columns = match &columns {
+                Some(columns) => columns,
+                None if is_header(&record) => {
+                    let found = Columns::from_header(&record, path)?;
+                    for name in found.unknown.iter() {
+                        entries.push(Entry { position, event: Err(ProblemKind::UnknownColumn(name.clone())) });
+                    }
+                    columns = Some(found);
+                    continue;
+                },
+                None => columns.insert(Columns::positional()),
+            };
+
+            entries.push(Entry { position, event: columns.parse(&record) });
+        }
+        Ok(entries)
+    }
+
+    fn write(&self, events: &[Event]) -> Result<(), DaysError> {
+        self.check_writable()?;
+        let path = self.path.as_path();
+        let error = |err| csv_error(err, path, true);
+
+        let mut writer = Writer::from_writer(Vec::new());
+        writer.write_record(CURRENT_COLUMNS).map_err(error)?;
+        for event in events.iter() {
+            let recurrence = event.recurrence.map(|r| r.to_string()).unwrap_or_default();
+            writer.write_record(&[event.date.to_string(), event.category.clone(), event.description.clone(), recurrence])
+                .map_err(error)?;
+        }
+        let contents = match writer.into_inner() {
+            Ok(contents) => contents,
+            Err(err) => return Err(DaysError::WriteError { path: path.to_path_buf(), source: err.into_error() }),
+        };
+        replace_file(path, &contents)
+    }
+
+    fn check_writable(&self) -> Result<(), DaysError> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        match self.first_row()? {
+            Some(record) if is_header(&record) => {
+                let columns = Columns::from_header(&record, &self.path)?;
+                if columns.unknown.is_empty() {
+                    Ok(())
+                }
+                else {
+                    Err(DaysError::UnknownColumns { path: self.path.clone(), columns: columns.unknown })
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn outdated_layout(&self) -> Result<Option<String>, DaysError> {
+        match self.layout()? {
+            CURRENT_LAYOUT => Ok(None),
+            layout => Ok(Some(layout.to_string())),
+        }
+    }
+}
+
+/// Converts an error from the CSV reader or writer, keeping I/O errors
+/// apart from malformed data.
+fn csv_error(err: csv::Error, path: &Path, writing: bool) -> DaysError {
+    let path = path.to_path_buf();
+    let line = err.position().map(|position| position.line());
+    if !err.is_io_error() {
+        return DaysError::CsvError { path, line, source: err };
+    }
+    match err.into_kind() {
+        csv::ErrorKind::Io(source) if writing => DaysError::WriteError { path, source },
+        csv::ErrorKind::Io(source) => DaysError::ReadError { path, source },
+        _ => unreachable!("checked to be an I/O error"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::testing::TempFile;
+
+    /// A store for the contents, with the file that is removed when it is dropped.
+    fn store(name: &str, contents: &str) -> (CsvStore, TempFile) {
+        let file = TempFile::new(&format!("{}.csv", name), contents);
+        (CsvStore::new(file.path()), file)
+    }
+
+    fn record(fields: &[&str]) -> StringRecord {
+        StringRecord::from(fields.to_vec())
+    }
+
+    #[test]
+    fn recognizes_headers() {
+        assert!(is_header(&record(&["date", "category", "description"])));
+        assert!(is_header(&record(&[" Category", " DATE ", "description"])));
+        assert!(!is_header(&record(&["2024-01-01", "work", "Start"])));
+        assert!(!is_header(&record(&["2024-13-01", "work", "Start"])));
+        assert!(!is_header(&record(&["yesterday", "work", "Start"])));
+        assert!(!is_header(&record(&["2024-05-01", "personal", "date"])));
+    }
+
+    #[test]
+    fn detects_layouts() {
+        let layout = |name, contents| store(name, contents).0.layout().unwrap();
+        assert_eq!(layout("headerless", "2024-01-01,work,Start\n"), CsvLayout::Headerless);
+        assert_eq!(layout("v1", "date,category,description\n2024-01-01,work,Start\n"), CsvLayout::V1);
+        assert_eq!(layout("v2", "recurrence,date,category,description\n"), CsvLayout::V2);
+        assert_eq!(layout("empty", ""), CURRENT_LAYOUT);
+        assert_eq!(layout("date-event", "2024-05-01,personal,date\n2024-06-01,work,Launch\n"), CsvLayout::Headerless);
+    }
+
+    #[test]
+    fn a_bad_first_date_is_a_bad_row() {
+        let (store, _file) = store("bad-first", "2024-13-01,work,Start\n2024-01-02,work,Next\n");
+        let entries = store.read().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].event, Err(ProblemKind::InvalidDate("2024-13-01".to_string())));
+        assert!(entries[1].event.is_ok());
+    }
+
+    #[test]
+    fn unknown_columns_are_not_written_over() {
+        let (store, _file) = store("unknown", "date,category,description,notes\n2024-01-01,work,Start,Bring cake\n");
+        let entries = store.read().unwrap();
+        assert_eq!(entries[0].event, Err(ProblemKind::UnknownColumn("notes".to_string())));
+        assert!(entries[1].event.is_ok());
+        assert!(matches!(store.write(&[]), Err(DaysError::UnknownColumns { .. })));
+        assert_eq!(store.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn a_header_without_required_columns_is_an_error() {
+        let (store, _file) = store("no-category", "date,description\n");
+        assert!(store.layout().is_err());
+    }
+}
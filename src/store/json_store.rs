@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::{Event, DaysError};
+use crate::storage::replace_file;
+use super::{Document, Entry, EventStore};
+
+/// Events in a JSON file, as a list under `events`:
+/// `{"events": [{"date": "2026-10-16", "category": ..., "description": ...}]}`.
+#[derive(Debug, Clone)]
+pub struct JsonStore {
+    path: PathBuf,
+}
+
+impl JsonStore {
+    pub fn new(path: &Path) -> Self {
+        JsonStore { path: path.to_path_buf() }
+    }
+
+    fn document(&self) -> Result<Document, DaysError> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(source) => return Err(DaysError::ReadError { path: self.path.clone(), source }),
+        };
+        match serde_json::from_str::<Document>(&text) {
+            Ok(document) => Ok(document),
+            Err(err) => Err(DaysError::FormatError { path: self.path.clone(), message: err.to_string() }),
+        }
+    }
+}
+
+impl EventStore for JsonStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<Vec<Entry>, DaysError> {
+        Ok(self.document()?.entries())
+    }
+
+    fn write(&self, events: &[Event]) -> Result<(), DaysError> {
+        self.check_writable()?;
+        let mut text = match serde_json::to_string_pretty(&Document::new(events)) {
+            Ok(text) => text,
+            Err(err) => return Err(DaysError::FormatError { path: self.path.clone(), message: err.to_string() }),
+        };
+        text.push('\n');
+        replace_file(&self.path, text.as_bytes())
+    }
+
+    fn check_writable(&self) -> Result<(), DaysError> {
+        // A missing or empty file has nothing to lose
+        match fs::metadata(&self.path) {
+            Ok(metadata) if metadata.len() > 0 => {},
+            _ => return Ok(()),
+        }
+        self.document()?.check_writable(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::ProblemKind;
+    use crate::store::testing::TempFile;
+
+    fn event(date: &str, recurrence: Option<&str>) -> Event {
+        Event {
+            date: date.parse().unwrap(),
+            category: "work".to_string(),
+            description: "Start, \\ \"quoted\"".to_string(),
+            recurrence: recurrence.map(|rule| rule.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn events_round_trip() {
+        let file = TempFile::new("round-trip.json", "");
+        let store = JsonStore::new(file.path());
+        let events = vec![event("2024-01-01", None), event("2024-02-29", Some("yearly;until=2030-01-01"))];
+        store.write(&events).unwrap();
+        let read: Vec<Event> = store.read().unwrap().into_iter().map(|entry| entry.event.unwrap()).collect();
+        assert_eq!(read, events);
+    }
+
+    #[test]
+    fn unknown_fields_are_not_written_over() {
+        let file = TempFile::new("unknown.json", "{\"owner\": \"me\", \"events\": [{\"date\": \"2024-01-01\", \"category\": \"work\", \"description\": \"Start\", \"notes\": \"Cake\"}]}");
+        let store = JsonStore::new(file.path());
+        let problems: Vec<(u64, ProblemKind)> = store.read().unwrap().into_iter()
+            .filter_map(|entry| entry.event.err().map(|problem| (entry.position, problem)))
+            .collect();
+        assert_eq!(problems, [
+            (0, ProblemKind::UnknownField("owner".to_string())),
+            (1, ProblemKind::UnknownField("notes".to_string())),
+        ]);
+        assert!(matches!(store.write(&[]), Err(DaysError::UnknownColumns { .. })));
+        assert_eq!(file.contents(), "{\"owner\": \"me\", \"events\": [{\"date\": \"2024-01-01\", \"category\": \"work\", \"description\": \"Start\", \"notes\": \"Cake\"}]}");
+    }
+}
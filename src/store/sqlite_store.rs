@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use rusqlite::{params, Connection};
+use crate::{Event, DaysError};
+use super::{Entry, EventStore, parse_fields};
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY,
+    date TEXT NOT NULL,
+    category TEXT NOT NULL,
+    description TEXT NOT NULL,
+    recurrence TEXT
+)";
+
+/// Events in the `events` table of an SQLite database. The columns
+/// are like in the CSV files, and the position of an entry is its row id.
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+    path: PathBuf,
+}
+
+impl SqliteStore {
+    pub fn new(path: &Path) -> Self {
+        SqliteStore { path: path.to_path_buf() }
+    }
+
+    fn error(&self, err: rusqlite::Error) -> DaysError {
+        DaysError::FormatError { path: self.path.clone(), message: err.to_string() }
+    }
+
+    fn open(&self) -> Result<Connection, DaysError> {
+        let connection = Connection::open(&self.path).map_err(|err| self.error(err))?;
+        connection.execute(CREATE_TABLE, []).map_err(|err| self.error(err))?;
+        Ok(connection)
+    }
+}
+
+impl EventStore for SqliteStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<Vec<Entry>, DaysError> {
+        let connection = self.open()?;
+        let mut statement = connection
+            .prepare("SELECT id, date, category, description, recurrence FROM events ORDER BY id")
+            .map_err(|err| self.error(err))?;
+        let rows = statement
+            .query_map([], |row| {
+                let id: i64 = row.get(0)?;
+                let date: String = row.get(1)?;
+                let category: String = row.get(2)?;
+                let description: String = row.get(3)?;
+                let recurrence: Option<String> = row.get(4)?;
+                Ok(Entry {
+                    position: id as u64,
+                    event: parse_fields(&date, &category, &description, recurrence.as_deref()),
+                })
+            })
+            .map_err(|err| self.error(err))?;
+
+        let mut entries: Vec<Entry> = Vec::new();
+        for row in rows {
+            entries.push(row.map_err(|err| self.error(err))?);
+        }
+        Ok(entries)
+    }
+
+    /// Replaces the events in one transaction, so that other readers
+    /// see either all of the old events or all of the new ones.
+    fn write(&self, events: &[Event]) -> Result<(), DaysError> {
+        let mut connection = self.open()?;
+        let transaction = connection.transaction().map_err(|err| self.error(err))?;
+        transaction.execute("DELETE FROM events", []).map_err(|err| self.error(err))?;
+        for event in events.iter() {
+            transaction.execute(
+                "INSERT INTO events (date, category, description, recurrence) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    event.date.to_string(),
+                    event.category,
+                    event.description,
+                    event.recurrence.map(|recurrence| recurrence.to_string()),
+                ],
+            ).map_err(|err| self.error(err))?;
+        }
+        transaction.commit().map_err(|err| self.error(err))
+    }
+}
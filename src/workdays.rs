@@ -0,0 +1,196 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use chrono::{NaiveDate, Datelike, Weekday};
+use crate::{Event, DaysError};
+use crate::calc::add_days;
+
+/// The weekend when none is configured.
+pub const DEFAULT_WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+/// Which days are business days: every day that is not on
+/// the weekend and not a holiday.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    pub weekend: Vec<Weekday>,
+    /// Holidays on fixed dates, like from a holidays file.
+    pub holidays: BTreeSet<NaiveDate>,
+    /// Holidays from events, which may repeat.
+    pub holiday_events: Vec<Event>,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar {
+            weekend: DEFAULT_WEEKEND.to_vec(),
+            holidays: BTreeSet::new(),
+            holiday_events: Vec::new(),
+        }
+    }
+}
+
+impl Calendar {
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date) || self.holiday_events.iter().any(|event| {
+            match event.effective_recurrence() {
+                Some(recurrence) => recurrence.next_occurrence(event.date, date) == Some(date),
+                None => event.date == date,
+            }
+        })
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.weekend.contains(&date.weekday()) && !self.is_holiday(date)
+    }
+
+    /// Returns the signed number of business days from `from` to `to`,
+    /// counting the days after `from` up to and including `to`, so that
+    /// from a Friday to the next Monday is one business day.
+    pub fn business_days_between(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        let (first, last, sign) = if from <= to { (from, to, 1) } else { (to, from, -1) };
+        let count = first.iter_days()
+            .skip(1)
+            .take_while(|date| *date <= last)
+            .filter(|date| self.is_business_day(*date))
+            .count() as i64;
+        sign * count
+    }
+
+    /// Returns the date `count` business days after `date`, or before
+    /// it if the count is negative. Returns `None` if the calendar has
+    /// no business days at all, or if the date goes out of range.
+    pub fn add_business_days(&self, date: NaiveDate, count: i64) -> Option<NaiveDate> {
+        // Business days are at least as far apart as calendar days, so
+        // there is no need to count them one by one to find this out.
+        if self.weekend.len() >= 7 || add_days(date, count).is_none() {
+            return None;
+        }
+
+        let mut date = date;
+        let mut remaining = count.unsigned_abs();
+        while remaining > 0 {
+            date = if count > 0 { date.succ_opt()? } else { date.pred_opt()? };
+            if self.is_business_day(date) {
+                remaining -= 1;
+            }
+        }
+        Some(date)
+    }
+}
+
+/// Reads holidays from a file with one date (YYYY-MM-DD) per line.
+/// Empty lines and lines starting with `#` are skipped, and anything
+/// after the date on a line is taken to be the name of the holiday.
+pub fn read_holidays(path: &Path) -> Result<BTreeSet<NaiveDate>, DaysError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(source) => return Err(DaysError::HolidaysReadError { path: path.to_path_buf(), source }),
+    };
+
+    let mut holidays: BTreeSet<NaiveDate> = BTreeSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let value = line.split_whitespace().next().unwrap_or(line);
+        match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => {
+                holidays.insert(date);
+            },
+            Err(_) => {
+                return Err(DaysError::InvalidHoliday {
+                    path: path.to_path_buf(),
+                    line: index as u64 + 1,
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+    Ok(holidays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::Recurrence;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn holiday(date: NaiveDate, recurrence: Option<&str>) -> Event {
+        Event {
+            date,
+            category: "holiday".to_string(),
+            description: "Holiday".to_string(),
+            recurrence: recurrence.map(|rule| rule.parse::<Recurrence>().unwrap()),
+        }
+    }
+
+    #[test]
+    fn weekends_are_not_business_days() {
+        let calendar = Calendar::default();
+        // Friday June 7, 2024 to Monday June 10.
+        assert_eq!(calendar.business_days_between(date(2024, 6, 7), date(2024, 6, 10)), 1);
+        assert_eq!(calendar.business_days_between(date(2024, 6, 3), date(2024, 6, 10)), 5);
+        assert_eq!(calendar.business_days_between(date(2024, 6, 8), date(2024, 6, 9)), 0);
+        assert_eq!(calendar.add_business_days(date(2024, 6, 7), 1), Some(date(2024, 6, 10)));
+        assert_eq!(calendar.add_business_days(date(2024, 6, 8), 1), Some(date(2024, 6, 10)));
+    }
+
+    #[test]
+    fn the_weekend_can_be_configured() {
+        let calendar = Calendar { weekend: vec![Weekday::Fri, Weekday::Sat], ..Calendar::default() };
+        assert_eq!(calendar.business_days_between(date(2024, 6, 6), date(2024, 6, 9)), 1);
+        let weekend = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+        let always = Calendar { weekend, ..Calendar::default() };
+        assert_eq!(always.add_business_days(date(2024, 6, 7), 1), None);
+    }
+
+    #[test]
+    fn holidays_on_weekdays_are_not_business_days() {
+        let calendar = Calendar { holidays: BTreeSet::from([date(2024, 6, 6)]), ..Calendar::default() };
+        assert!(!calendar.is_business_day(date(2024, 6, 6)));
+        assert_eq!(calendar.business_days_between(date(2024, 6, 5), date(2024, 6, 7)), 1);
+        assert_eq!(calendar.add_business_days(date(2024, 6, 5), 1), Some(date(2024, 6, 7)));
+    }
+
+    #[test]
+    fn holiday_events_can_repeat() {
+        let calendar = Calendar {
+            holiday_events: vec![holiday(date(2020, 12, 24), Some("yearly")), holiday(date(2024, 6, 6), None)],
+            ..Calendar::default()
+        };
+        assert!(calendar.is_holiday(date(2024, 12, 24)));
+        assert!(calendar.is_holiday(date(2024, 6, 6)));
+        assert!(!calendar.is_holiday(date(2025, 6, 6)));
+        assert!(!calendar.is_holiday(date(2019, 12, 24)));
+        // Tuesday December 24, 2024 is skipped.
+        assert_eq!(calendar.add_business_days(date(2024, 12, 23), 1), Some(date(2024, 12, 25)));
+    }
+
+    #[test]
+    fn counts_backwards() {
+        let calendar = Calendar::default();
+        assert_eq!(calendar.business_days_between(date(2024, 6, 10), date(2024, 6, 7)), -1);
+        assert_eq!(calendar.add_business_days(date(2024, 6, 10), -1), Some(date(2024, 6, 7)));
+        assert_eq!(calendar.add_business_days(date(2024, 6, 10), -5), Some(date(2024, 6, 3)));
+    }
+
+    #[test]
+    fn the_same_date_is_zero_business_days() {
+        let calendar = Calendar::default();
+        assert_eq!(calendar.business_days_between(date(2024, 6, 5), date(2024, 6, 5)), 0);
+        assert_eq!(calendar.business_days_between(date(2024, 6, 8), date(2024, 6, 8)), 0);
+        assert_eq!(calendar.add_business_days(date(2024, 6, 8), 0), Some(date(2024, 6, 8)));
+    }
+
+    #[test]
+    fn counts_out_of_range_are_none() {
+        let calendar = Calendar::default();
+        assert_eq!(calendar.add_business_days(date(2024, 6, 5), i64::MIN), None);
+        assert_eq!(calendar.add_business_days(date(2024, 6, 5), i64::MAX), None);
+        assert_eq!(calendar.add_business_days(NaiveDate::MAX, 1), None);
+    }
+}